@@ -0,0 +1,21 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn time<T>(iterations: u32, f: impl Fn() -> T) -> Duration {
+  let start = Instant::now();
+  for _ in 0..iterations {
+    black_box(f());
+  }
+  start.elapsed() / iterations
+}
+
+pub fn compare<T, U>(
+  iterations: u32,
+  (first_name, first): (&str, impl Fn() -> T),
+  (second_name, second): (&str, impl Fn() -> U),
+) {
+  let first_time = time(iterations, first);
+  let second_time = time(iterations, second);
+  println!("{first_name}: {first_time:?} per run");
+  println!("{second_name}: {second_time:?} per run");
+}
@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+pub fn has_flag(args: &[String], flag: &str) -> bool {
+  args.iter().any(|a| a == flag)
+}
+
+pub fn get_option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+  args
+    .iter()
+    .position(|a| a == name)
+    .and_then(|i| args.get(i + 1))
+    .map(String::as_str)
+}
+
+pub fn get_options<'a>(args: &'a [String], name: &str) -> Vec<&'a str> {
+  args
+    .iter()
+    .zip(args.iter().skip(1))
+    .filter(|(a, _)| *a == name)
+    .map(|(_, value)| value.as_str())
+    .collect()
+}
+
+pub fn refuse_invalid<T: Display>(args: &[String], what: &str, errors: &[T]) -> bool {
+  for error in errors.iter() {
+    eprintln!("{error}");
+  }
+  let refuse = !errors.is_empty() && !has_flag(args, "--force");
+  if refuse {
+    eprintln!("Refusing to score an invalid {what}, pass --force to proceed anyway");
+  }
+  refuse
+}
+
+#[cfg(test)]
+pub fn args(line: &str) -> Vec<String> {
+  line.split_whitespace().map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn options() {
+    let args = args("--symbols # --parts");
+    assert_eq!(get_option(&args, "--symbols"), Some("#"));
+    assert_eq!(get_option(&args, "--parts"), None);
+    assert_eq!(get_option(&args, "--aggregate"), None);
+  }
+
+  #[test]
+  fn repeated_options() {
+    let args = args("--start exact:AAA --start suffix:A --target");
+    assert_eq!(get_options(&args, "--start"), vec!["exact:AAA", "suffix:A"]);
+    assert!(get_options(&args, "--target").is_empty());
+  }
+
+  #[test]
+  fn refusal() {
+    let forced = args("--force");
+    assert!(refuse_invalid(&[], "file", &["line 1: broken"]));
+    assert!(!refuse_invalid(&forced, "file", &["line 1: broken"]));
+    assert!(!refuse_invalid::<&str>(&[], "file", &[]));
+  }
+
+  #[test]
+  fn flags() {
+    let args = args("--report --machine");
+    assert!(has_flag(&args, "--report"));
+    assert!(!has_flag(&args, "--csv"));
+  }
+}
@@ -1,5 +1,9 @@
-use num::integer::lcm;
+use crate::cli;
+use itertools::Itertools;
+use num::integer::{ExtendedGcd, Integer};
+use regex::Regex;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
 use std::str::FromStr;
@@ -40,16 +44,313 @@ impl FromStr for Instruction {
   }
 }
 
+fn parse_id(id: &str, line: &str) -> Result<String, String> {
+  let id = id.trim();
+  if !id.is_empty() && id.chars().all(char::is_alphanumeric) {
+    Ok(id.to_string())
+  } else {
+    Err(format!("invalid node id {id:?} in {line:?}"))
+  }
+}
+
 impl FromStr for Node {
-  type Err = ();
+  type Err = String;
 
   fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (id, edges) = s
+      .split_once('=')
+      .ok_or_else(|| format!("expected ID = (LEFT, RIGHT), found {s:?}"))?;
+    let edges = edges
+      .trim()
+      .strip_prefix('(')
+      .and_then(|e| e.strip_suffix(')'))
+      .ok_or_else(|| format!("expected parenthesised edges in {s:?}"))?;
+    let (left, right) = edges
+      .split_once(',')
+      .ok_or_else(|| format!("expected two comma separated edges in {s:?}"))?;
+    Ok(Self {
+      id: parse_id(id, s)?,
+      left: parse_id(left, s)?,
+      right: parse_id(right, s)?,
+    })
+  }
+}
+
+#[derive(Debug)]
+struct Network {
+  instructions: Vec<Instruction>,
+  nodes: Vec<Node>,
+  index: HashMap<String, usize>,
+}
+
+impl Network {
+  fn from_input(input: &Input) -> Result<Self, String> {
+    let mut iter_input = input.iter();
+    let instructions: Vec<Instruction> = iter_input
+      .next()
+      .ok_or("missing instructions")?
+      .trim()
+      .chars()
+      .enumerate()
+      .map(|(i, c)| {
+        Instruction::from_str(&c.to_string())
+          .map_err(|_| format!("line 1: invalid instruction {c:?} at position {}", i + 1))
+      })
+      .collect::<Result<_, _>>()?;
+    if instructions.is_empty() {
+      return Err("line 1: missing instructions".to_string());
+    }
+
+    let mut nodes: Vec<Node> = vec![];
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut lines: Vec<usize> = vec![];
+    for (i, l) in iter_input.enumerate() {
+      let line = i + 2;
+      if l.trim().is_empty() {
+        continue;
+      }
+      let node = Node::from_str(l).map_err(|e| format!("line {line}: {e}"))?;
+      if index.insert(node.id.clone(), nodes.len()).is_some() {
+        return Err(format!("line {line}: node {} is defined twice", node.id));
+      }
+      nodes.push(node);
+      lines.push(line);
+    }
+
+    for (node, line) in nodes.iter().zip(lines) {
+      for next in [&node.left, &node.right] {
+        if !index.contains_key(next) {
+          return Err(format!(
+            "line {line}: node {} references undefined node {next}",
+            node.id
+          ));
+        }
+      }
+    }
+
     Ok(Self {
-      id: s[0..3].to_string(),
-      left: s[7..10].to_string(),
-      right: s[12..15].to_string(),
+      instructions,
+      nodes,
+      index,
     })
   }
+
+  fn compile(&self) -> CompiledNetwork {
+    let edges = self
+      .nodes
+      .iter()
+      .map(|n| [Instruction::Left, Instruction::Right].map(|i| self.index[n.next(&i)] as NodeId))
+      .collect();
+    let instructions = self
+      .instructions
+      .iter()
+      .map(|i| match i {
+        Instruction::Left => 0,
+        Instruction::Right => 1,
+      })
+      .collect();
+    CompiledNetwork {
+      edges,
+      instructions,
+    }
+  }
+}
+
+type NodeId = u32;
+
+#[derive(Debug)]
+struct CompiledNetwork {
+  edges: Vec<[NodeId; 2]>,
+  instructions: Vec<u8>,
+}
+
+impl CompiledNetwork {
+  fn reachable(&self, start: NodeId) -> Vec<NodeId> {
+    let mut seen: Vec<bool> = vec![false; self.edges.len()];
+    let mut order: Vec<NodeId> = vec![start];
+    seen[start as usize] = true;
+    let mut i = 0;
+    while i < order.len() {
+      for next in self.edges[order[i] as usize] {
+        if !seen[next as usize] {
+          seen[next as usize] = true;
+          order.push(next);
+        }
+      }
+      i += 1;
+    }
+    order
+  }
+
+  fn steps_until(&self, start: NodeId, targets: &[bool]) -> Result<usize, Vec<NodeId>> {
+    let reachable = self.reachable(start);
+    if !reachable.iter().any(|&n| targets[n as usize]) {
+      return Err(reachable);
+    }
+
+    let len = self.instructions.len();
+    let mut seen_states: Vec<bool> = vec![false; self.edges.len() * len];
+    let mut seen_nodes: Vec<bool> = vec![false; self.edges.len()];
+    let mut visited: Vec<NodeId> = vec![];
+    let mut node = start;
+    let mut i = 0;
+    let mut steps: usize = 0;
+    loop {
+      let state = node as usize * len + i;
+      if seen_states[state] {
+        return Err(visited);
+      }
+      seen_states[state] = true;
+      if !seen_nodes[node as usize] {
+        seen_nodes[node as usize] = true;
+        visited.push(node);
+      }
+      node = self.edges[node as usize][self.instructions[i] as usize];
+      steps += 1;
+      if targets[node as usize] {
+        return Ok(steps);
+      }
+      i += 1;
+      if i == len {
+        i = 0;
+      }
+    }
+  }
+
+  fn cycle(&self, start: NodeId, targets: &[bool]) -> GhostCycle {
+    let len = self.instructions.len();
+    let mut first_seen: Vec<Option<usize>> = vec![None; self.edges.len() * len];
+    let mut node = start;
+    let mut hits: Vec<usize> = vec![];
+    let mut t: usize = 0;
+    loop {
+      let state = node as usize * len + t % len;
+      if let Some(cycle_start) = first_seen[state] {
+        return GhostCycle {
+          start: cycle_start,
+          length: t - cycle_start,
+          hits,
+        };
+      }
+      first_seen[state] = Some(t);
+      if targets[node as usize] {
+        hits.push(t);
+      }
+      node = self.edges[node as usize][self.instructions[t % len] as usize];
+      t += 1;
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum WalkError {
+  UnknownStart(String),
+  Unreachable {
+    start: String,
+    target: String,
+    visited: Vec<String>,
+  },
+}
+
+impl Display for WalkError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::UnknownStart(start) => write!(f, "no start node matches {start}"),
+      Self::Unreachable {
+        start,
+        target,
+        visited,
+      } => write!(
+        f,
+        "{target} cannot be reached from {start}, visited {}",
+        visited.join(", ")
+      ),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct GhostCycle {
+  start: usize,
+  length: usize,
+  hits: Vec<usize>,
+}
+
+impl GhostCycle {
+  fn pre_cycle_hits(&self) -> impl Iterator<Item = usize> + '_ {
+    self.hits.iter().copied().filter(|&t| t < self.start)
+  }
+
+  fn cycle_hits(&self) -> impl Iterator<Item = usize> + '_ {
+    self.hits.iter().copied().filter(|&t| t >= self.start)
+  }
+
+  fn hits_at(&self, t: usize) -> bool {
+    let t = if t < self.start {
+      t
+    } else {
+      self.start + (t - self.start) % self.length
+    };
+    self.hits.binary_search(&t).is_ok()
+  }
+}
+
+impl Display for GhostCycle {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let hits: Vec<String> = self.hits.iter().map(usize::to_string).collect();
+    write!(
+      f,
+      "cycle start {}, cycle length {}, target offsets [{}]",
+      self.start,
+      self.length,
+      hits.join(", ")
+    )
+  }
+}
+
+fn merge_congruences(a: (i128, i128), b: (i128, i128)) -> Option<(i128, i128)> {
+  let ((a1, m1), (a2, m2)) = (a, b);
+  let ExtendedGcd { gcd, x, .. } = m1.extended_gcd(&m2);
+  if (a2 - a1) % gcd != 0 {
+    return None;
+  }
+  let modulus = m1 / gcd * m2;
+  let k = ((a2 - a1) / gcd * x).rem_euclid(m2 / gcd);
+  Some(((a1 + m1 * k).rem_euclid(modulus), modulus))
+}
+
+fn solve_cycles(cycles: &[GhostCycle]) -> Option<usize> {
+  let pre_cycle = cycles
+    .iter()
+    .flat_map(GhostCycle::pre_cycle_hits)
+    .filter(|&t| t > 0)
+    .filter(|&t| cycles.iter().all(|c| c.hits_at(t)))
+    .min();
+
+  let lower = cycles.iter().map(|c| c.start).max().unwrap_or(0).max(1) as i128;
+  let periodic = cycles
+    .iter()
+    .map(|c| c.cycle_hits().collect::<Vec<_>>())
+    .multi_cartesian_product()
+    .filter_map(|residues| {
+      residues
+        .iter()
+        .zip(cycles.iter())
+        .map(|(&r, c)| (r as i128, c.length as i128))
+        .try_fold((0, 1), merge_congruences)
+    })
+    .map(|(x, m)| {
+      let x = x.rem_euclid(m);
+      if x >= lower {
+        x
+      } else {
+        x + (lower - x + m - 1) / m * m
+      }
+    })
+    .min()
+    .map(|t| t as usize);
+
+  pre_cycle.into_iter().chain(periodic).min()
 }
 
 impl Node {
@@ -61,91 +362,284 @@ impl Node {
   }
 }
 
-static START: &str = "AAA";
-static TARGET: &str = "ZZZ";
+#[derive(Debug, Clone)]
+enum NodePattern {
+  Exact(String),
+  Prefix(String),
+  Suffix(String),
+  Regex(Regex),
+}
 
-fn initial(input: Input) -> usize {
-  let mut iter_input = input.iter();
-  let instructions: Vec<Instruction> = iter_input
-    .next()
-    .unwrap()
-    .chars()
-    .map(|c| c.to_string())
-    .map(|c| Instruction::from_str(&c).unwrap())
-    .collect();
-  iter_input.next(); // left blank
-  let nodes: Vec<Node> = iter_input.map(|s| Node::from_str(s).unwrap()).collect();
-  let map: HashMap<String, Node> = nodes.into_iter().fold(Default::default(), |mut acc, node| {
-    acc.insert(node.id.clone(), node);
-    acc
-  });
-  let mut current_pos = START.to_string();
-  let mut steps: usize = 0;
-  for i in instructions.iter().cycle() {
-    steps += 1;
-    let node = map.get(&current_pos).unwrap();
-    let next = node.next(i);
-    if next == TARGET {
-      break;
+impl NodePattern {
+  fn matches(&self, id: &str) -> bool {
+    match self {
+      Self::Exact(s) => id == s,
+      Self::Prefix(s) => id.starts_with(s.as_str()),
+      Self::Suffix(s) => id.ends_with(s.as_str()),
+      Self::Regex(r) => r.is_match(id),
     }
-    current_pos = next.to_string();
   }
+}
+
+impl FromStr for NodePattern {
+  type Err = String;
 
-  steps
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (kind, value) = s
+      .split_once(':')
+      .ok_or_else(|| format!("expected KIND:VALUE, found {s:?}"))?;
+    match kind {
+      "exact" => Ok(Self::Exact(value.to_string())),
+      "prefix" => Ok(Self::Prefix(value.to_string())),
+      "suffix" => Ok(Self::Suffix(value.to_string())),
+      "regex" => Regex::new(value)
+        .map(Self::Regex)
+        .map_err(|e| format!("invalid regex {value:?}: {e}")),
+      _ => Err(format!("unknown pattern kind {kind:?} in {s:?}")),
+    }
+  }
 }
 
-fn extra(input: Input) -> usize {
-  let mut iter_input = input.iter();
-  let instructions: Vec<Instruction> = iter_input
-    .next()
-    .unwrap()
-    .chars()
-    .map(|c| c.to_string())
-    .map(|c| Instruction::from_str(&c).unwrap())
-    .collect();
-  iter_input.next(); // left blank
-  let nodes: Vec<Node> = iter_input.map(|s| Node::from_str(s).unwrap()).collect();
-  let mut current_positions: Vec<String> = nodes
+impl Display for NodePattern {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Exact(s) => write!(f, "{s}"),
+      Self::Prefix(s) => write!(f, "{s}*"),
+      Self::Suffix(s) => write!(f, "*{s}"),
+      Self::Regex(r) => write!(f, "/{r}/"),
+    }
+  }
+}
+
+fn matches_any(patterns: &[NodePattern], id: &str) -> bool {
+  patterns.iter().any(|p| p.matches(id))
+}
+
+fn describe(patterns: &[NodePattern]) -> String {
+  patterns.iter().map(NodePattern::to_string).join(" or ")
+}
+
+fn target_mask(network: &Network, targets: &[NodePattern]) -> Vec<bool> {
+  network
+    .nodes
     .iter()
-    .map(|n| &n.id)
-    .filter(|s| s.ends_with("A"))
-    .cloned()
-    .collect();
-  let map: HashMap<String, Node> = nodes.into_iter().fold(Default::default(), |mut acc, node| {
-    acc.insert(node.id.clone(), node);
-    acc
-  });
+    .map(|n| matches_any(targets, &n.id))
+    .collect()
+}
 
-  let min_steps = current_positions
+fn start_ids(network: &Network, starts: &[NodePattern]) -> Vec<NodeId> {
+  network
+    .nodes
     .iter()
-    .map(|s| {
-      let mut current_pos = s.clone();
-      let mut steps: usize = 0;
-      for i in instructions.iter().cycle() {
-        steps += 1;
-        let node = map.get(&current_pos).unwrap();
-        let next = node.next(i);
-        if next.ends_with("Z") {
-          break;
-        }
-        current_pos = next.to_string();
+    .enumerate()
+    .filter(|(_, n)| matches_any(starts, &n.id))
+    .map(|(i, _)| i as NodeId)
+    .collect()
+}
+
+static START: &str = "AAA";
+static TARGET: &str = "ZZZ";
+static GHOST_START: &str = "A";
+static GHOST_TARGET: &str = "Z";
+
+fn walk(network: &Network, start: &str, targets: &[NodePattern]) -> Result<usize, WalkError> {
+  let start_id = *network
+    .index
+    .get(start)
+    .ok_or_else(|| WalkError::UnknownStart(start.to_string()))?;
+  network
+    .compile()
+    .steps_until(start_id as NodeId, &target_mask(network, targets))
+    .map_err(|visited| WalkError::Unreachable {
+      start: start.to_string(),
+      target: describe(targets),
+      visited: visited
+        .iter()
+        .map(|&n| network.nodes[n as usize].id.clone())
+        .collect(),
+    })
+}
+
+fn initial(input: Input) -> usize {
+  let network = Network::from_input(&input).unwrap();
+  walk(&network, START, &[NodePattern::Exact(TARGET.to_string())]).unwrap()
+}
+
+#[cfg(test)]
+fn extra(input: Input) -> usize {
+  let network = Network::from_input(&input).unwrap();
+  let starts = [NodePattern::Suffix(GHOST_START.to_string())];
+  let targets = [NodePattern::Suffix(GHOST_TARGET.to_string())];
+  ghost_steps(&network, &starts, &targets).expect("no solution")
+}
+
+fn to_dot(
+  network: &Network,
+  starts: &[NodePattern],
+  targets: &[NodePattern],
+  reachable_only: bool,
+) -> String {
+  let is_start = |id: &str| matches_any(starts, id);
+  let is_target = |id: &str| matches_any(targets, id);
+  let mut included: Vec<bool> = vec![!reachable_only; network.nodes.len()];
+  if reachable_only {
+    let compiled = network.compile();
+    for start in start_ids(network, starts) {
+      for n in compiled.reachable(start) {
+        included[n as usize] = true;
       }
-      steps
+    }
+  }
+
+  let nodes: Vec<&Node> = network
+    .nodes
+    .iter()
+    .zip(included)
+    .filter(|(_, included)| *included)
+    .map(|(n, _)| n)
+    .collect();
+  let mut lines: Vec<String> = vec!["digraph network {".to_string()];
+  for n in nodes.iter() {
+    if is_start(&n.id) {
+      lines.push(format!(
+        "  \"{}\" [style=filled, fillcolor=palegreen];",
+        n.id
+      ));
+    } else if is_target(&n.id) {
+      lines.push(format!(
+        "  \"{}\" [style=filled, fillcolor=lightcoral];",
+        n.id
+      ));
+    }
+  }
+  for n in nodes.iter() {
+    lines.push(format!(
+      "  \"{}\" -> \"{}\" [label=\"L\", color=blue];",
+      n.id, n.left
+    ));
+    lines.push(format!(
+      "  \"{}\" -> \"{}\" [label=\"R\", color=red, style=dashed];",
+      n.id, n.right
+    ));
+  }
+  lines.push("}".to_string());
+  lines.join("\n") + "\n"
+}
+
+fn ghost_cycles(
+  network: &Network,
+  starts: &[NodePattern],
+  targets: &[NodePattern],
+) -> Vec<(String, GhostCycle)> {
+  let compiled = network.compile();
+  let targets = target_mask(network, targets);
+  start_ids(network, starts)
+    .into_iter()
+    .map(|start| {
+      let id = network.nodes[start as usize].id.clone();
+      (id, compiled.cycle(start, &targets))
     })
-    .fold(1, lcm);
+    .collect()
+}
 
-  min_steps
+fn ghost_steps(
+  network: &Network,
+  starts: &[NodePattern],
+  targets: &[NodePattern],
+) -> Option<usize> {
+  let cycles: Vec<GhostCycle> = ghost_cycles(network, starts, targets)
+    .into_iter()
+    .map(|(_, c)| c)
+    .collect();
+  solve_cycles(&cycles)
 }
 
-pub fn solve() {
-  let input = read_data(false);
-  let score = extra(input);
-  println!("{score}")
+fn parse_patterns(
+  args: &[String],
+  name: &str,
+  default: NodePattern,
+) -> Result<Vec<NodePattern>, String> {
+  let patterns: Vec<NodePattern> = cli::get_options(args, name)
+    .into_iter()
+    .map(NodePattern::from_str)
+    .collect::<Result<_, _>>()?;
+  if patterns.is_empty() {
+    Ok(vec![default])
+  } else {
+    Ok(patterns)
+  }
+}
+
+pub fn solve(args: &[String]) {
+  let input = read_data(cli::has_flag(args, "--test"));
+  let network = match Network::from_input(&input) {
+    Ok(network) => network,
+    Err(e) => {
+      eprintln!("Invalid network: {e}");
+      return;
+    }
+  };
+  let (default_start, default_target) = if cli::has_flag(args, "--single") {
+    (
+      NodePattern::Exact(START.to_string()),
+      NodePattern::Exact(TARGET.to_string()),
+    )
+  } else {
+    (
+      NodePattern::Suffix(GHOST_START.to_string()),
+      NodePattern::Suffix(GHOST_TARGET.to_string()),
+    )
+  };
+  let patterns = parse_patterns(args, "--start", default_start).and_then(|starts| {
+    let targets = parse_patterns(args, "--target", default_target)?;
+    Ok((starts, targets))
+  });
+  let (starts, targets) = match patterns {
+    Ok(patterns) => patterns,
+    Err(e) => {
+      eprintln!("Invalid pattern: {e}");
+      return;
+    }
+  };
+  if let Some(path) = cli::get_option(args, "--dot") {
+    let dot = to_dot(
+      &network,
+      &starts,
+      &targets,
+      cli::has_flag(args, "--reachable"),
+    );
+    if let Err(e) = std::fs::write(path, dot) {
+      eprintln!("Cannot write DOT file {path}: {e}");
+    }
+    return;
+  }
+  let start_nodes = start_ids(&network, &starts);
+  if start_nodes.is_empty() {
+    eprintln!("{}", WalkError::UnknownStart(describe(&starts)));
+    return;
+  }
+  if cli::has_flag(args, "--cycles") {
+    for (start, cycle) in ghost_cycles(&network, &starts, &targets) {
+      println!("{start}: {cycle}");
+    }
+  }
+  if let [start] = start_nodes[..] {
+    match walk(&network, &network.nodes[start as usize].id, &targets) {
+      Ok(steps) => println!("{steps}"),
+      Err(e) => eprintln!("{e}"),
+    }
+    return;
+  }
+  match ghost_steps(&network, &starts, &targets) {
+    Some(steps) => println!("{steps}"),
+    None => println!("no solution"),
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use indoc::indoc;
 
   #[test]
   fn parse() {
@@ -159,6 +653,303 @@ mod tests {
     assert_eq!(n, expected)
   }
 
+  #[test]
+  fn parse_flexible() {
+    let n = Node::from_str("  11A=(1B ,XYZW  )").unwrap();
+    let expected = Node {
+      id: "11A".to_string(),
+      left: "1B".to_string(),
+      right: "XYZW".to_string(),
+    };
+    assert_eq!(n, expected);
+    assert_eq!(
+      Node::from_str("AAA = BBB, CCC"),
+      Err("expected parenthesised edges in \"AAA = BBB, CCC\"".to_string())
+    );
+    assert_eq!(
+      Node::from_str("AAA = (B-B, CCC)"),
+      Err("invalid node id \"B-B\" in \"AAA = (B-B, CCC)\"".to_string())
+    );
+    assert!(Node::from_str("AAA").is_err());
+    assert!(Node::from_str("AAA = (BBB)").is_err());
+  }
+
+  #[test]
+  fn network() {
+    let input = read_data(true);
+    let network = Network::from_input(&input).unwrap();
+    assert_eq!(network.instructions.len(), 3);
+    assert_eq!(network.nodes.len(), 3);
+
+    let input: Input = indoc! {"
+      LR
+
+      AAA = (BBB, ZZZ)
+      BBB = (AAA, CCC)
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    assert_eq!(
+      Network::from_input(&input).unwrap_err(),
+      "line 3: node AAA references undefined node ZZZ"
+    );
+    let input: Input = indoc! {"
+      LX
+
+      AAA = (AAA, AAA)
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    assert_eq!(
+      Network::from_input(&input).unwrap_err(),
+      "line 1: invalid instruction 'X' at position 2"
+    );
+  }
+
+  #[test]
+  fn compile() {
+    let network = Network::from_input(&read_data(true)).unwrap();
+    let compiled = network.compile();
+    assert_eq!(compiled.edges, vec![[1, 1], [0, 2], [2, 2]]);
+    assert_eq!(compiled.instructions, vec![0, 0, 1]);
+  }
+
+  #[test]
+  fn real_answers() {
+    assert_eq!(initial(read_data(false)), 12737);
+    assert_eq!(extra(read_data(false)), 9064949303801);
+  }
+
+  #[test]
+  fn cycles() {
+    let input: Input = indoc! {"
+      LR
+
+      11A = (11B, XXX)
+      11B = (XXX, 11Z)
+      11Z = (11B, XXX)
+      22A = (22B, XXX)
+      22B = (22C, 22C)
+      22C = (22Z, 22Z)
+      22Z = (22B, 22B)
+      XXX = (XXX, XXX)
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    let network = Network::from_input(&input).unwrap();
+    let starts = [NodePattern::Suffix("A".to_string())];
+    let targets = [NodePattern::Suffix("Z".to_string())];
+    let cycles = ghost_cycles(&network, &starts, &targets);
+    assert_eq!(
+      cycles[0].1,
+      GhostCycle {
+        start: 1,
+        length: 2,
+        hits: vec![2]
+      }
+    );
+    assert_eq!(
+      cycles[1].1,
+      GhostCycle {
+        start: 1,
+        length: 6,
+        hits: vec![3, 6]
+      }
+    );
+    assert_eq!(
+      cycles[1].1.to_string(),
+      "cycle start 1, cycle length 6, target offsets [3, 6]"
+    );
+    assert_eq!(ghost_steps(&network, &starts, &targets), Some(6));
+  }
+
+  #[test]
+  fn cycle_solver() {
+    let offset = GhostCycle {
+      start: 3,
+      length: 4,
+      hits: vec![1, 5],
+    };
+    let other = GhostCycle {
+      start: 0,
+      length: 6,
+      hits: vec![1, 3],
+    };
+    assert_eq!(solve_cycles(&[offset, other]), Some(1));
+
+    let even = GhostCycle {
+      start: 0,
+      length: 4,
+      hits: vec![2],
+    };
+    let odd = GhostCycle {
+      start: 0,
+      length: 6,
+      hits: vec![3],
+    };
+    assert_eq!(solve_cycles(&[even, odd]), None);
+
+    let a = GhostCycle {
+      start: 2,
+      length: 4,
+      hits: vec![3],
+    };
+    let b = GhostCycle {
+      start: 0,
+      length: 6,
+      hits: vec![1],
+    };
+    assert_eq!(solve_cycles(&[a, b]), Some(7));
+    assert_eq!(merge_congruences((3, 4), (1, 6)), Some((7, 12)));
+  }
+
+  #[test]
+  fn unreachable() {
+    let input: Input = indoc! {"
+      LR
+
+      AAA = (BBB, CCC)
+      BBB = (AAA, AAA)
+      CCC = (CCC, BBB)
+      ZZZ = (ZZZ, ZZZ)
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    let network = Network::from_input(&input).unwrap();
+    let err = walk(&network, START, &[NodePattern::Exact(TARGET.to_string())]).unwrap_err();
+    assert_eq!(
+      err,
+      WalkError::Unreachable {
+        start: "AAA".to_string(),
+        target: "ZZZ".to_string(),
+        visited: vec!["AAA".to_string(), "BBB".to_string(), "CCC".to_string()],
+      }
+    );
+    assert_eq!(
+      err.to_string(),
+      "ZZZ cannot be reached from AAA, visited AAA, BBB, CCC"
+    );
+    assert_eq!(
+      walk(&network, "QQQ", &[NodePattern::Exact(TARGET.to_string())]),
+      Err(WalkError::UnknownStart("QQQ".to_string()))
+    );
+    assert_eq!(
+      WalkError::UnknownStart("QQQ".to_string()).to_string(),
+      "no start node matches QQQ"
+    );
+
+    let input: Input = indoc! {"
+      LL
+
+      AAA = (BBB, ZZZ)
+      BBB = (AAA, AAA)
+      ZZZ = (ZZZ, ZZZ)
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    let network = Network::from_input(&input).unwrap();
+    assert_eq!(
+      walk(&network, START, &[NodePattern::Exact(TARGET.to_string())])
+        .unwrap_err()
+        .to_string(),
+      "ZZZ cannot be reached from AAA, visited AAA, BBB"
+    );
+  }
+
+  #[test]
+  fn dot() {
+    let input: Input = indoc! {"
+      LR
+
+      AAA = (BBB, ZZZ)
+      BBB = (AAA, AAA)
+      ZZZ = (ZZZ, ZZZ)
+      XXX = (ZZZ, XXX)
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    let network = Network::from_input(&input).unwrap();
+    let starts = [NodePattern::Suffix("A".to_string())];
+    let targets = [NodePattern::Suffix("Z".to_string())];
+    let dot = to_dot(&network, &starts, &targets, true);
+    assert_eq!(
+      dot,
+      [
+        "digraph network {",
+        "  \"AAA\" [style=filled, fillcolor=palegreen];",
+        "  \"ZZZ\" [style=filled, fillcolor=lightcoral];",
+        "  \"AAA\" -> \"BBB\" [label=\"L\", color=blue];",
+        "  \"AAA\" -> \"ZZZ\" [label=\"R\", color=red, style=dashed];",
+        "  \"BBB\" -> \"AAA\" [label=\"L\", color=blue];",
+        "  \"BBB\" -> \"AAA\" [label=\"R\", color=red, style=dashed];",
+        "  \"ZZZ\" -> \"ZZZ\" [label=\"L\", color=blue];",
+        "  \"ZZZ\" -> \"ZZZ\" [label=\"R\", color=red, style=dashed];",
+        "}",
+        "",
+      ]
+      .join("\n")
+    );
+    let dot = to_dot(&network, &starts, &targets, false);
+    assert!(dot.contains("  \"XXX\" -> \"ZZZ\" [label=\"L\", color=blue];"));
+  }
+
+  #[test]
+  fn patterns() {
+    let exact = NodePattern::from_str("exact:BCA").unwrap();
+    assert!(exact.matches("BCA"));
+    assert!(!exact.matches("BCAA"));
+    let prefix = NodePattern::from_str("prefix:BC").unwrap();
+    assert!(prefix.matches("BCQ"));
+    let suffix = NodePattern::from_str("suffix:Q").unwrap();
+    assert!(suffix.matches("BCQ"));
+    assert!(!suffix.matches("QBC"));
+    let regex = NodePattern::from_str("regex:^[0-9]+Q$").unwrap();
+    assert!(regex.matches("12Q"));
+    assert!(!regex.matches("A2Q"));
+    assert_eq!(
+      describe(&[exact, suffix, regex]),
+      "BCA or *Q or /^[0-9]+Q$/"
+    );
+    assert!(NodePattern::from_str("BCA").is_err());
+    assert!(NodePattern::from_str("glob:B*").is_err());
+    assert!(NodePattern::from_str("regex:(").is_err());
+
+    let input: Input = indoc! {"
+      LR
+
+      BCA = (BCB, XXQ)
+      BCB = (BCA, 12Q)
+      XXQ = (XXQ, XXQ)
+      12Q = (12Q, 12Q)
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    let network = Network::from_input(&input).unwrap();
+    let targets = [NodePattern::from_str("suffix:Q").unwrap()];
+    assert_eq!(walk(&network, "BCA", &targets), Ok(2));
+    let targets = [NodePattern::from_str("regex:^[0-9]").unwrap()];
+    assert_eq!(walk(&network, "BCA", &targets), Ok(2));
+    let targets = [NodePattern::from_str("prefix:XX").unwrap()];
+    assert_eq!(
+      walk(&network, "BCA", &targets).unwrap_err().to_string(),
+      "XX* cannot be reached from BCA, visited BCA, BCB, 12Q"
+    );
+
+    let args = cli::args("--target exact:12Q --target prefix:XX");
+    let default = NodePattern::Suffix("Z".to_string());
+    let targets = parse_patterns(&args, "--target", default.clone()).unwrap();
+    assert_eq!(describe(&targets), "12Q or XX*");
+    let starts = parse_patterns(&args, "--start", default).unwrap();
+    assert_eq!(describe(&starts), "*Z");
+  }
+
   #[test]
   fn simple() {
     let input = read_data(true);
@@ -1,5 +1,9 @@
+use crate::cli;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
+use std::str::FromStr;
 
 type Input = Vec<String>;
 type Tag = usize;
@@ -25,15 +29,55 @@ struct RangeGame {
   mappers: Vec<Mapper>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Rng {
   from: Tag,
   to: Tag,
 }
 
+impl FromStr for Rng {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let parse = |s: &str| s.trim().parse::<Tag>().map_err(|_| ());
+    let (from, to) = match s.split_once("..") {
+      Some((from, to)) => (parse(from)?, parse(to)?),
+      None => {
+        let from = parse(s)?;
+        (from, from.checked_add(1).ok_or(())?)
+      }
+    };
+    if from >= to {
+      return Err(());
+    }
+    Ok(Rng { from, to })
+  }
+}
+
+impl Display for Rng {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}..{}", self.from, self.to)
+  }
+}
+
+fn merge_ranges(mut ranges: Vec<Rng>) -> Vec<Rng> {
+  ranges.sort_by_key(|r| r.from);
+  let mut merged: Vec<Rng> = vec![];
+  for r in ranges {
+    match merged.last_mut() {
+      Some(last) if r.from <= last.to => last.to = last.to.max(r.to),
+      _ => merged.push(r),
+    }
+  }
+  merged
+}
+
 #[derive(Debug)]
 struct Mapper {
-  title: String,
+  source: String,
+  destination: String,
   ranges: Vec<RangeMapper>,
+  table: Vec<Segment>,
 }
 
 #[derive(Debug)]
@@ -41,6 +85,33 @@ struct RangeMapper {
   from: Tag,
   to: Tag,
   len: usize,
+  line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+  start: Tag,
+  end: Tag,
+  diff: isize,
+}
+
+fn shift(x: Tag, diff: isize) -> Tag {
+  x.saturating_add_signed(diff)
+}
+
+impl Segment {
+  fn preimage(&self, r: &Rng) -> Option<Rng> {
+    let from = shift(self.start, self.diff).max(r.from);
+    let to = shift(self.end, self.diff).min(r.to);
+    if from < to {
+      Some(Rng {
+        from: shift(from, -self.diff),
+        to: shift(to, -self.diff),
+      })
+    } else {
+      None
+    }
+  }
 }
 
 impl RangeGame {
@@ -71,14 +142,16 @@ impl Game {
     it.next(); //empty line
 
     let mut mappers: Vec<Mapper> = Vec::new();
+    let mut line = 3;
     loop {
       let block_it = it.by_ref().take_while(|l| !l.is_empty());
       let slice = block_it.collect::<Vec<_>>();
       if slice.is_empty() {
         break;
       }
-      let mapper = Mapper::from_input(&slice);
+      let mapper = Mapper::from_input(&slice, line);
       mappers.push(mapper);
+      line += slice.len() + 1;
     }
 
     Self { input, mappers }
@@ -92,6 +165,7 @@ impl Game {
       .collect()
   }
 
+  #[cfg(test)]
   fn play(&self) -> Vec<Tag> {
     self
       .input
@@ -99,41 +173,377 @@ impl Game {
       .map(|x| self.mappers.iter().fold(*x, |acc, m| m.map_input(acc)))
       .collect()
   }
+
+  fn compose(&self) -> Mapper {
+    let mut it = self.mappers.iter();
+    let first = it.next().unwrap();
+    let first = Mapper::from_segments(
+      first.source.clone(),
+      first.destination.clone(),
+      first.segments(),
+    );
+    it.fold(first, |acc, m| acc.compose(m))
+  }
+
+  fn check_chain(&self) -> Result<(), String> {
+    let mut current = "seed";
+    for m in self.mappers.iter() {
+      if m.source != current {
+        return Err(format!(
+          "expected a map from {current}, found {}-to-{} map",
+          m.source, m.destination
+        ));
+      }
+      current = &m.destination;
+    }
+    if current != "location" {
+      return Err(format!("chain ends at {current} instead of location"));
+    }
+    Ok(())
+  }
+
+  fn map_between(&self, value: Tag, from: &str, to: &str) -> Result<Tag, String> {
+    let mut current = from;
+    let mut value = value;
+    for _ in 0..self.mappers.len() {
+      if current == to {
+        return Ok(value);
+      }
+      let m = self
+        .mappers
+        .iter()
+        .find(|m| m.source == current)
+        .ok_or(format!("no map from {current}"))?;
+      value = m.map_input(value);
+      current = &m.destination;
+    }
+    if current == to {
+      Ok(value)
+    } else {
+      Err(format!("{to} cannot be reached from {from}"))
+    }
+  }
+
+  fn reverse(&self, location: Rng) -> Vec<Rng> {
+    self.mappers.iter().rev().fold(vec![location], |acc, m| {
+      merge_ranges(acc.iter().flat_map(|r| m.preimage(r)).collect())
+    })
+  }
+
+  fn validate(&self) -> Vec<Issue> {
+    self.mappers.iter().flat_map(Mapper::validate).collect()
+  }
+
+  fn play_composed(&self) -> Vec<Tag> {
+    let composed = self.compose();
+    let mut order: Vec<usize> = (0..self.input.len()).collect();
+    order.sort_by_key(|&i| self.input[i]);
+    let sorted: Vec<Tag> = order.iter().map(|&i| self.input[i]).collect();
+    let mut result: Vec<Tag> = vec![0; sorted.len()];
+    for (&i, value) in order.iter().zip(composed.map_batch(&sorted)) {
+      result[i] = value;
+    }
+    result
+  }
 }
 
 impl Mapper {
-  fn from_input(input: &[String]) -> Self {
+  fn new(source: String, destination: String, ranges: Vec<RangeMapper>) -> Self {
+    let mut ranges: Vec<(usize, RangeMapper)> = ranges.into_iter().enumerate().collect();
+    ranges.sort_by_key(|(_, r)| r.from);
+    let table = Self::build_table(&ranges);
+    Self {
+      source,
+      destination,
+      ranges: ranges.into_iter().map(|(_, r)| r).collect(),
+      table,
+    }
+  }
+
+  fn build_table(sorted: &[(usize, RangeMapper)]) -> Vec<Segment> {
+    let mut ends: Vec<(Tag, usize)> = sorted
+      .iter()
+      .map(|(priority, r)| (r.max_input(), *priority))
+      .collect();
+    ends.sort_unstable();
+    let mut bounds: Vec<Tag> = vec![0, Tag::MAX];
+    bounds.extend(sorted.iter().map(|(_, r)| r.from));
+    bounds.extend(ends.iter().map(|(end, _)| *end));
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut active: BTreeMap<usize, isize> = BTreeMap::new();
+    let (mut next_start, mut next_end) = (0, 0);
+    let mut table: Vec<Segment> = vec![];
+    for w in bounds.windows(2) {
+      while next_start < sorted.len() && sorted[next_start].1.from <= w[0] {
+        let (priority, r) = &sorted[next_start];
+        active.insert(*priority, r.diff());
+        next_start += 1;
+      }
+      while next_end < ends.len() && ends[next_end].0 <= w[0] {
+        active.remove(&ends[next_end].1);
+        next_end += 1;
+      }
+      table.push(Segment {
+        start: w[0],
+        end: w[1],
+        diff: active.values().next().copied().unwrap_or(0),
+      });
+    }
+    table
+  }
+
+  fn from_input(input: &[String], line: usize) -> Self {
     let mut it = input.iter();
-    let title = it.next().unwrap().clone();
-    let ranges = it.map(|s| RangeMapper::from_line(s)).collect::<Vec<_>>();
-    Self { title, ranges }
+    let (source, destination) = Self::parse_title(it.next().unwrap());
+    let ranges = it
+      .enumerate()
+      .map(|(i, s)| RangeMapper::from_line(s, line + 1 + i))
+      .collect::<Vec<_>>();
+    Self::new(source, destination, ranges)
+  }
+
+  fn parse_title(title: &str) -> (String, String) {
+    let name = title.trim().trim_end_matches("map:").trim();
+    let (source, destination) = name.split_once("-to-").unwrap();
+    (source.to_string(), destination.to_string())
   }
 
   fn map_input(&self, input: Tag) -> Tag {
-    self
+    let i = self.table.partition_point(|s| s.end <= input);
+    self.table.get(i).map_or(input, |s| shift(input, s.diff))
+  }
+
+  fn map_batch(&self, sorted: &[Tag]) -> Vec<Tag> {
+    let mut i = 0;
+    sorted
+      .iter()
+      .map(|&x| {
+        while i < self.table.len() && self.table[i].end <= x {
+          i += 1;
+        }
+        self.table.get(i).map_or(x, |s| shift(x, s.diff))
+      })
+      .collect()
+  }
+
+  fn preimage(&self, r: &Rng) -> Vec<Rng> {
+    let ranges = self
+      .segments()
+      .iter()
+      .filter_map(|s| s.preimage(r))
+      .collect();
+    merge_ranges(ranges)
+  }
+
+  fn segments(&self) -> &[Segment] {
+    &self.table
+  }
+
+  fn from_segments(source: String, destination: String, segments: &[Segment]) -> Self {
+    let mut merged: Vec<Segment> = vec![];
+    for s in segments.iter() {
+      match merged.last_mut() {
+        Some(last) if last.end == s.start && last.diff == s.diff => last.end = s.end,
+        _ => merged.push(*s),
+      }
+    }
+    let ranges = merged
+      .iter()
+      .filter(|s| s.diff != 0)
+      .map(|s| RangeMapper {
+        from: s.start,
+        to: shift(s.start, s.diff),
+        len: s.end - s.start,
+        line: 0,
+      })
+      .collect();
+    Self::new(source, destination, ranges)
+  }
+
+  fn compose(&self, next: &Mapper) -> Mapper {
+    let next_segments = next.segments();
+    let mut segments: Vec<Segment> = vec![];
+    for a in self.segments().iter() {
+      let image_start = shift(a.start, a.diff);
+      let image_end = shift(a.end, a.diff);
+      let first = next_segments.partition_point(|b| b.end <= image_start);
+      for b in next_segments[first..].iter() {
+        if b.start >= image_end {
+          break;
+        }
+        let start = image_start.max(b.start);
+        let end = image_end.min(b.end);
+        segments.push(Segment {
+          start: shift(start, -a.diff),
+          end: shift(end, -a.diff),
+          diff: a.diff + b.diff,
+        });
+      }
+    }
+    Mapper::from_segments(self.source.clone(), next.destination.clone(), &segments)
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Issue {
+  SourceOverlap {
+    map: String,
+    first: usize,
+    second: usize,
+    overlap: Rng,
+  },
+  DestinationOverlap {
+    map: String,
+    first: usize,
+    second: usize,
+    overlap: Rng,
+  },
+  Gap {
+    map: String,
+    line: usize,
+    gap: Rng,
+  },
+}
+
+impl Issue {
+  fn is_error(&self) -> bool {
+    !matches!(self, Self::Gap { .. })
+  }
+}
+
+impl Display for Issue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::SourceOverlap {
+        map,
+        first,
+        second,
+        overlap,
+      } => write!(
+        f,
+        "{map}: source ranges on lines {first} and {second} overlap on {overlap}"
+      ),
+      Self::DestinationOverlap {
+        map,
+        first,
+        second,
+        overlap,
+      } => write!(
+        f,
+        "{map}: destination ranges on lines {first} and {second} overlap on {overlap}"
+      ),
+      Self::Gap { map, line, gap } => write!(f, "{map}: identity gap {gap} before line {line}"),
+    }
+  }
+}
+
+fn find_overlaps(mut ranges: Vec<(Rng, usize)>) -> Vec<(usize, usize, Rng)> {
+  ranges.sort_by_key(|(r, line)| (r.from, *line));
+  let mut overlaps = vec![];
+  for (i, (a, a_line)) in ranges.iter().enumerate() {
+    for (b, b_line) in ranges[i + 1..].iter().take_while(|(b, _)| b.from < a.to) {
+      let overlap = Rng {
+        from: b.from,
+        to: a.to.min(b.to),
+      };
+      overlaps.push((*a_line.min(b_line), *a_line.max(b_line), overlap));
+    }
+  }
+  overlaps
+}
+
+impl Mapper {
+  fn name(&self) -> String {
+    format!("{}-to-{} map", self.source, self.destination)
+  }
+
+  fn validate(&self) -> Vec<Issue> {
+    let sources: Vec<(Rng, usize)> = self
       .ranges
       .iter()
-      .find_map(|r| r.map_value(input))
-      .unwrap_or(input)
+      .map(|r| {
+        let source = Rng {
+          from: r.from,
+          to: r.max_input(),
+        };
+        (source, r.line)
+      })
+      .collect();
+    let destinations: Vec<(Rng, usize)> = self
+      .ranges
+      .iter()
+      .map(|r| {
+        let destination = Rng {
+          from: r.to,
+          to: r.to + r.len,
+        };
+        (destination, r.line)
+      })
+      .collect();
+
+    let mut issues: Vec<Issue> = vec![];
+    for (first, second, overlap) in find_overlaps(sources.clone()) {
+      issues.push(Issue::SourceOverlap {
+        map: self.name(),
+        first,
+        second,
+        overlap,
+      });
+    }
+    for (first, second, overlap) in find_overlaps(destinations) {
+      issues.push(Issue::DestinationOverlap {
+        map: self.name(),
+        first,
+        second,
+        overlap,
+      });
+    }
+
+    let mut sorted = sources;
+    sorted.sort_by_key(|(r, _)| r.from);
+    let mut cursor: Tag = 0;
+    for (r, line) in sorted {
+      if r.from > cursor {
+        issues.push(Issue::Gap {
+          map: self.name(),
+          line,
+          gap: Rng {
+            from: cursor,
+            to: r.from,
+          },
+        });
+      }
+      cursor = cursor.max(r.to);
+    }
+    issues
+  }
+}
+
+impl Display for Mapper {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "{}:", self.name())?;
+    writeln!(f, "{:>12} {:>12} {:>12}", "start", "end", "offset")?;
+    for r in self.ranges.iter() {
+      writeln!(f, "{:>12} {:>12} {:>+12}", r.from, r.max_input(), r.diff())?;
+    }
+    Ok(())
   }
 }
 
 impl RangeMapper {
-  fn from_line(input: &str) -> Self {
+  fn from_line(input: &str, line: usize) -> Self {
     let mut it = input.split_whitespace();
     let local_parse = |s: &str| s.parse::<Tag>().unwrap();
     let to = local_parse(it.next().unwrap());
     let from = local_parse(it.next().unwrap());
     let len = local_parse(it.next().unwrap());
-    Self { from, to, len }
-  }
-
-  fn map_value(&self, x: Tag) -> Option<Tag> {
-    if self.input_is_in_range(x) {
-      let res = x as isize + self.diff();
-      Some(res as Tag)
-    } else {
-      None
+    Self {
+      from,
+      to,
+      len,
+      line,
     }
   }
 
@@ -154,7 +564,7 @@ impl RangeMapper {
 
 fn initial(input: Input) -> usize {
   let game = Game::from_input(input);
-  let mut result = game.play();
+  let mut result = game.play_composed();
   result.sort();
 
   let first = result.first().unwrap();
@@ -165,8 +575,57 @@ fn extra(input: Input) -> usize {
   unimplemented!()
 }
 
-pub fn solve() {
-  let input = read_data(false);
+pub fn solve(args: &[String]) {
+  let input = read_data(cli::has_flag(args, "--test"));
+  let game = Game::from_input(input.clone());
+  if let Err(e) = game.check_chain() {
+    eprintln!("Invalid almanac: {e}");
+    return;
+  }
+  let issues = game.validate();
+  if cli::has_flag(args, "--validate") {
+    for issue in issues.iter() {
+      println!("{issue}");
+    }
+    return;
+  }
+  let errors: Vec<&Issue> = issues.iter().filter(|i| i.is_error()).collect();
+  if cli::refuse_invalid(args, "almanac", &errors) {
+    return;
+  }
+  if let Some(from) = cli::get_option(args, "--from") {
+    let to = cli::get_option(args, "--to").unwrap_or("location");
+    let value = match cli::get_option(args, "--value").map(str::parse::<Tag>) {
+      Some(Ok(value)) => value,
+      Some(Err(_)) => {
+        eprintln!("Invalid --value, expected a number");
+        return;
+      }
+      None => {
+        eprintln!("Missing --value");
+        return;
+      }
+    };
+    match game.map_between(value, from, to) {
+      Ok(result) => println!("{result}"),
+      Err(e) => eprintln!("{e}"),
+    }
+    return;
+  }
+  if let Some(location) = cli::get_option(args, "--reverse") {
+    let Ok(location) = Rng::from_str(location) else {
+      eprintln!("Invalid --reverse, expected a location like 35 or 30..40");
+      return;
+    };
+    for seeds in game.reverse(location) {
+      println!("{seeds}");
+    }
+    return;
+  }
+  if cli::has_flag(args, "--compose") {
+    print!("{}", game.compose());
+    return;
+  }
   let score = initial(input);
   println!("{score}")
 }
@@ -174,6 +633,7 @@ pub fn solve() {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use indoc::indoc;
 
   #[test]
   fn parsing() {
@@ -185,27 +645,303 @@ mod tests {
 
   #[test]
   fn mapping() {
-    let mapper = Mapper {
-      title: "Mapper".to_string(),
-      ranges: vec![
+    let mapper = Mapper::new(
+      "seed".to_string(),
+      "soil".to_string(),
+      vec![
         RangeMapper {
           to: 50,
           from: 98,
           len: 2,
+          line: 0,
         },
         RangeMapper {
           to: 52,
           from: 50,
           len: 48,
+          line: 0,
         },
       ],
-    };
+    );
 
     assert_eq!(mapper.map_input(98), 50);
     assert_eq!(mapper.map_input(1), 1);
     assert_eq!(mapper.map_input(53), 55);
   }
 
+  #[test]
+  fn batch() {
+    let game = Game::from_input(read_data(false));
+    let mut seeds = game.input.clone();
+    seeds.sort();
+    for m in game.mappers.iter() {
+      let expected: Vec<Tag> = seeds.iter().map(|&x| m.map_input(x)).collect();
+      assert_eq!(m.map_batch(&seeds), expected);
+      assert!(m.ranges.windows(2).all(|w| w[0].from <= w[1].from));
+      assert!(m.table.windows(2).all(|w| w[0].end == w[1].start));
+    }
+  }
+
+  #[test]
+  fn overlapping_ranges() {
+    let game = Game::from_input(
+      indoc! {"
+        seeds: 5 8
+
+        seed-to-soil map:
+        100 0 10
+        200 3 4
+
+        soil-to-location map:
+        0 105 2
+      "}
+      .lines()
+      .map(String::from)
+      .collect(),
+    );
+    let mapper = &game.mappers[0];
+    assert_eq!(mapper.map_input(5), 105);
+    assert_eq!(mapper.map_input(8), 108);
+    assert_eq!(mapper.map_input(12), 12);
+    assert_eq!(mapper.map_batch(&[3, 5, 8, 10]), vec![103, 105, 108, 10]);
+    let composed = game.compose();
+    for x in 0..20 {
+      let expected = game.mappers.iter().fold(x, |acc, m| m.map_input(acc));
+      assert_eq!(composed.map_input(x), expected);
+    }
+    assert_eq!(game.play_composed(), vec![0, 108]);
+
+    let mapper = Mapper::new(
+      "a".to_string(),
+      "b".to_string(),
+      vec![
+        RangeMapper {
+          to: 500,
+          from: 20,
+          len: 10,
+          line: 4,
+        },
+        RangeMapper {
+          to: 300,
+          from: 10,
+          len: 20,
+          line: 5,
+        },
+        RangeMapper {
+          to: 700,
+          from: 20,
+          len: 5,
+          line: 6,
+        },
+      ],
+    );
+    let froms: Vec<Tag> = mapper.ranges.iter().map(|r| r.from).collect();
+    assert_eq!(froms, vec![10, 20, 20]);
+    assert_eq!(mapper.ranges[1].line, 4);
+    for x in 0..40 {
+      let expected = if (20..30).contains(&x) {
+        x + 480
+      } else if (10..30).contains(&x) {
+        x + 290
+      } else {
+        x
+      };
+      assert_eq!(mapper.map_input(x), expected, "{x}");
+    }
+  }
+
+  #[test]
+  fn compose() {
+    let mapper = Mapper::new(
+      "a".to_string(),
+      "b".to_string(),
+      vec![RangeMapper {
+        to: 50,
+        from: 98,
+        len: 2,
+        line: 0,
+      }],
+    );
+    let next = Mapper::new(
+      "b".to_string(),
+      "c".to_string(),
+      vec![RangeMapper {
+        to: 0,
+        from: 45,
+        len: 10,
+        line: 0,
+      }],
+    );
+    let composed = mapper.compose(&next);
+    for x in 0..120 {
+      assert_eq!(composed.map_input(x), next.map_input(mapper.map_input(x)));
+    }
+    assert_eq!(
+      composed.to_string(),
+      [
+        "a-to-c map:",
+        "       start          end       offset",
+        "          45           55          -45",
+        "          98          100          -93",
+        ""
+      ]
+      .join("\n")
+    );
+  }
+
+  #[test]
+  fn compose_chain() {
+    for is_test in [true, false] {
+      let game = Game::from_input(read_data(is_test));
+      assert_eq!(game.play_composed(), game.play());
+    }
+    let game = Game::from_input(read_data(true));
+    let composed = game.compose();
+    for x in 0..200 {
+      let expected = game.mappers.iter().fold(x, |acc, m| m.map_input(acc));
+      assert_eq!(composed.map_input(x), expected);
+    }
+  }
+
+  #[test]
+  fn categories() {
+    let game = Game::from_input(read_data(true));
+    assert_eq!(game.mappers[1].source, "soil");
+    assert_eq!(game.mappers[1].destination, "fertilizer");
+    assert_eq!(game.check_chain(), Ok(()));
+    assert_eq!(game.map_between(79, "seed", "location"), Ok(82));
+    assert_eq!(game.map_between(81, "soil", "humidity"), Ok(78));
+    assert_eq!(game.map_between(81, "soil", "soil"), Ok(81));
+    assert_eq!(
+      game.map_between(81, "humidity", "soil"),
+      Err("no map from location".to_string())
+    );
+
+    let mut game = game;
+    game.mappers.swap(1, 2);
+    assert_eq!(
+      game.check_chain(),
+      Err("expected a map from soil, found fertilizer-to-water map".to_string())
+    );
+  }
+
+  #[test]
+  fn preimage() {
+    let segment = Segment {
+      start: 98,
+      end: 100,
+      diff: -48,
+    };
+    let r = Rng { from: 51, to: 60 };
+    assert_eq!(segment.preimage(&r), Some(Rng { from: 99, to: 100 }));
+    assert_eq!(segment.preimage(&Rng { from: 0, to: 50 }), None);
+
+    let mapper = Mapper::new(
+      "seed".to_string(),
+      "soil".to_string(),
+      vec![
+        RangeMapper {
+          to: 50,
+          from: 98,
+          len: 2,
+          line: 0,
+        },
+        RangeMapper {
+          to: 52,
+          from: 50,
+          len: 48,
+          line: 0,
+        },
+      ],
+    );
+    assert_eq!(
+      mapper.preimage(&r),
+      vec![Rng { from: 50, to: 58 }, Rng { from: 99, to: 100 }]
+    );
+  }
+
+  #[test]
+  fn reverse() {
+    let game = Game::from_input(read_data(true));
+    let seeds = game.reverse(Rng::from_str("35").unwrap());
+    let covers = |seeds: &[Rng], x: Tag| seeds.iter().any(|r| r.from <= x && x < r.to);
+    assert!(covers(&seeds, 13));
+    for location in 0..100 {
+      let seeds = game.reverse(Rng {
+        from: location,
+        to: location + 1,
+      });
+      for seed in 0..200 {
+        let expected = game.mappers.iter().fold(seed, |acc, m| m.map_input(acc));
+        assert_eq!(covers(&seeds, seed), expected == location);
+      }
+    }
+    assert_eq!(Rng::from_str("10..20"), Ok(Rng { from: 10, to: 20 }));
+    assert_eq!(Rng::from_str("x"), Err(()));
+    assert_eq!(Rng::from_str("40..30"), Err(()));
+    assert_eq!(Rng::from_str("30..30"), Err(()));
+    assert_eq!(Rng::from_str(&Tag::MAX.to_string()), Err(()));
+  }
+
+  #[test]
+  fn validation() {
+    let game = Game::from_input(read_data(true));
+    let issues = game.validate();
+    assert!(issues.iter().all(|i| !i.is_error()));
+    assert_eq!(
+      issues[0],
+      Issue::Gap {
+        map: "seed-to-soil map".to_string(),
+        line: 5,
+        gap: Rng { from: 0, to: 50 }
+      }
+    );
+    assert_eq!(
+      issues[0].to_string(),
+      "seed-to-soil map: identity gap 0..50 before line 5"
+    );
+    assert!(Game::from_input(read_data(false))
+      .validate()
+      .iter()
+      .all(|i| !i.is_error()));
+
+    let input: Input = indoc! {"
+      seeds: 1
+
+      seed-to-soil map:
+      10 0 5
+      12 3 4
+      0 20 2
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    let game = Game::from_input(input);
+    let map = "seed-to-soil map".to_string();
+    assert_eq!(
+      game.validate(),
+      vec![
+        Issue::SourceOverlap {
+          map: map.clone(),
+          first: 4,
+          second: 5,
+          overlap: Rng { from: 3, to: 5 }
+        },
+        Issue::DestinationOverlap {
+          map: map.clone(),
+          first: 4,
+          second: 5,
+          overlap: Rng { from: 12, to: 15 }
+        },
+        Issue::Gap {
+          map,
+          line: 6,
+          gap: Rng { from: 7, to: 20 }
+        },
+      ]
+    );
+  }
+
   #[test]
   fn simple() {
     let input = read_data(true);
@@ -1,49 +1,95 @@
-use std::collections::HashSet;
+use crate::bench;
+use crate::cli;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
 
 type Input = Vec<String>;
 
+const DENSE_BITS: usize = 128;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct NumberSet {
+  dense: u128,
+  sparse: Vec<usize>,
+}
+
+impl FromIterator<usize> for NumberSet {
+  fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+    let mut set = NumberSet::default();
+    for n in iter {
+      if n < DENSE_BITS {
+        set.dense |= 1 << n;
+      } else {
+        set.sparse.push(n);
+      }
+    }
+    set.sparse.sort_unstable();
+    set.sparse.dedup();
+    set
+  }
+}
+
+impl NumberSet {
+  fn intersection_count(&self, other: &Self) -> usize {
+    let dense = (self.dense & other.dense).count_ones() as usize;
+    if self.sparse.is_empty() || other.sparse.is_empty() {
+      return dense;
+    }
+    let sparse = self
+      .sparse
+      .iter()
+      .filter(|n| other.sparse.binary_search(n).is_ok())
+      .count();
+    dense + sparse
+  }
+}
+
 #[derive(Debug)]
 struct Card {
   id: usize,
-  numbers: HashSet<usize>,
-  winning: HashSet<usize>,
+  numbers: NumberSet,
+  winning: NumberSet,
   amount: usize,
 }
 
 impl Card {
   fn from_line(l: &str) -> Self {
-    let mut parts = l.split(":");
-    let id: usize = parts
-      .next()
-      .unwrap()
-      .split_whitespace()
-      .last()
-      .unwrap()
-      .parse()
-      .unwrap();
-    let mut parts = parts.next().unwrap().split("|");
-    let numbers = Card::extract_number_set(parts.next().unwrap());
-    let winning = Card::extract_number_set(parts.next().unwrap());
+    let (id, numbers, winning) = Card::parse_line(l).unwrap();
     Self {
       id,
-      numbers,
-      winning,
+      numbers: numbers.into_iter().collect(),
+      winning: winning.into_iter().collect(),
       amount: 1,
     }
   }
 
-  fn extract_number_set(text: &str) -> HashSet<usize> {
+  fn parse_line(l: &str) -> Result<(usize, Vec<usize>, Vec<usize>), String> {
+    let (title, lists) = l.split_once(":").ok_or("missing ':' after the card id")?;
+    let id = title.split_whitespace().last().ok_or("missing card id")?;
+    let id: usize = id.parse().map_err(|_| format!("invalid card id {id:?}"))?;
+    let (numbers, winning) = lists
+      .split_once("|")
+      .ok_or("missing '|' between the number lists")?;
+    let numbers = Card::extract_number_set(numbers)?;
+    let winning = Card::extract_number_set(winning)?;
+    Ok((id, numbers, winning))
+  }
+
+  fn extract_number_set<T: FromIterator<usize>>(text: &str) -> Result<T, String> {
     text
       .trim()
       .split_whitespace()
-      .map(|s| s.parse::<usize>().unwrap())
+      .map(|s| {
+        s.parse::<usize>()
+          .map_err(|_| format!("invalid number {s:?}"))
+      })
       .collect()
   }
 
   fn get_matches(&self) -> usize {
-    self.winning.intersection(&self.numbers).count()
+    self.winning.intersection_count(&self.numbers)
   }
 
   fn get_points(&self) -> usize {
@@ -56,6 +102,132 @@ impl Card {
   }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum Issue {
+  NonSequentialId {
+    line: usize,
+    expected: usize,
+    found: usize,
+  },
+  DuplicateId {
+    line: usize,
+    id: usize,
+    first_line: usize,
+  },
+  DuplicateNumber {
+    line: usize,
+    list: &'static str,
+    number: usize,
+  },
+  ListLength {
+    line: usize,
+    list: &'static str,
+    expected: usize,
+    found: usize,
+  },
+  Malformed {
+    line: usize,
+    reason: String,
+  },
+}
+
+impl Display for Issue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NonSequentialId {
+        line,
+        expected,
+        found,
+      } => write!(
+        f,
+        "line {line}: expected card {expected}, found card {found}"
+      ),
+      Self::DuplicateId {
+        line,
+        id,
+        first_line,
+      } => write!(
+        f,
+        "line {line}: card {id} is already defined on line {first_line}"
+      ),
+      Self::DuplicateNumber { line, list, number } => {
+        write!(f, "line {line}: number {number} repeated in {list} list")
+      }
+      Self::ListLength {
+        line,
+        list,
+        expected,
+        found,
+      } => write!(
+        f,
+        "line {line}: {list} list has {found} numbers, expected {expected}"
+      ),
+      Self::Malformed { line, reason } => write!(f, "line {line}: malformed card, {reason}"),
+    }
+  }
+}
+
+fn find_duplicates(list: &[usize]) -> Vec<usize> {
+  let mut seen: HashSet<usize> = HashSet::new();
+  let mut reported: HashSet<usize> = HashSet::new();
+  list
+    .iter()
+    .filter(|n| !seen.insert(**n) && reported.insert(**n))
+    .copied()
+    .collect()
+}
+
+fn validate(input: &Input) -> Vec<Issue> {
+  let mut issues: Vec<Issue> = vec![];
+  let mut first_lines: HashMap<usize, usize> = HashMap::new();
+  let mut lengths: Option<(usize, usize)> = None;
+  for (i, l) in input.iter().enumerate() {
+    let line = i + 1;
+    let (id, numbers, winning) = match Card::parse_line(l) {
+      Ok(card) => card,
+      Err(reason) => {
+        issues.push(Issue::Malformed { line, reason });
+        continue;
+      }
+    };
+    if id != line {
+      issues.push(Issue::NonSequentialId {
+        line,
+        expected: line,
+        found: id,
+      });
+    }
+    if let Some(&first_line) = first_lines.get(&id) {
+      issues.push(Issue::DuplicateId {
+        line,
+        id,
+        first_line,
+      });
+    } else {
+      first_lines.insert(id, line);
+    }
+
+    let (numbers_len, winning_len) = *lengths.get_or_insert((numbers.len(), winning.len()));
+    for (list, values, expected) in [
+      ("numbers", &numbers, numbers_len),
+      ("winning", &winning, winning_len),
+    ] {
+      for number in find_duplicates(values) {
+        issues.push(Issue::DuplicateNumber { line, list, number });
+      }
+      if values.len() != expected {
+        issues.push(Issue::ListLength {
+          line,
+          list,
+          expected,
+          found: values.len(),
+        });
+      }
+    }
+  }
+  issues
+}
+
 fn read_data(is_test: bool) -> Input {
   let extension = if is_test { "test.txt" } else { "txt" };
   let filename = format!("./resources/4.{}", extension);
@@ -74,36 +246,141 @@ fn initial(input: Input) -> usize {
     .sum()
 }
 
-fn extra(input: Input) -> usize {
+#[derive(Debug, PartialEq, Eq)]
+struct CardTrace {
+  id: usize,
+  matches: usize,
+  received: Vec<(usize, usize)>,
+  amount: usize,
+}
+
+impl CardTrace {
+  fn to_text(&self) -> String {
+    let received: Vec<String> = self
+      .received
+      .iter()
+      .map(|(from, copies)| format!("{copies} from card {from}"))
+      .collect();
+    let received = if received.is_empty() {
+      "no copies".to_string()
+    } else {
+      received.join(", ")
+    };
+    format!(
+      "Card {}: {} matches, received {}, final count {}",
+      self.id, self.matches, received, self.amount
+    )
+  }
+
+  fn to_csv(&self) -> String {
+    let received: Vec<String> = self
+      .received
+      .iter()
+      .map(|(from, copies)| format!("{from}:{copies}"))
+      .collect();
+    format!(
+      "{},{},{},{}",
+      self.id,
+      self.matches,
+      self.amount,
+      received.join(";")
+    )
+  }
+}
+
+static CSV_HEADER: &str = "card,matches,amount,received";
+
+fn play(cards: &mut [Card]) -> Vec<CardTrace> {
+  let mut received: Vec<Vec<(usize, usize)>> = vec![vec![]; cards.len()];
+  let mut matches: Vec<usize> = Vec::with_capacity(cards.len());
+  for i in 0..cards.len() {
+    let card = &cards[i];
+    let card_matches = card.get_matches();
+    matches.push(card_matches);
+    let (id, amount) = (card.id, card.amount);
+    let next_cards = cards[i + 1..].iter_mut().zip(received[i + 1..].iter_mut());
+    for (next_card, next_received) in next_cards.take(card_matches) {
+      next_card.amount += amount;
+      next_received.push((id, amount));
+    }
+  }
+
+  cards
+    .iter()
+    .zip(matches)
+    .zip(received)
+    .map(|((c, matches), received)| CardTrace {
+      id: c.id,
+      matches,
+      received,
+      amount: c.amount,
+    })
+    .collect()
+}
+
+fn trace(input: Input) -> Vec<CardTrace> {
   let mut cards: Vec<Card> = input
     .iter()
     .map(String::as_str)
     .map(Card::from_line)
     .collect();
+  play(&mut cards)
+}
 
-  let n = cards.len();
+fn extra(input: Input) -> usize {
+  trace(input).iter().map(|t| t.amount).sum()
+}
 
-  for i in 0..n {
-    let card = &cards[i];
-    let matches = card.get_matches();
-    if matches == 0 {
-      continue;
-    }
+fn hash_set_matches(l: &str) -> usize {
+  let mut parts = l.split(":").last().unwrap().split("|");
+  let numbers: HashSet<usize> = Card::extract_number_set(parts.next().unwrap()).unwrap();
+  let winning: HashSet<usize> = Card::extract_number_set(parts.next().unwrap()).unwrap();
+  winning.intersection(&numbers).count()
+}
 
-    let amount = card.amount;
-    for j in (i + 1)..=(i + matches) {
-      let next_card = cards.get_mut(j);
-      if next_card.is_none() {
-        continue;
+fn bench(input: &Input, iterations: u32) {
+  bench::compare(
+    iterations,
+    ("hash set", || {
+      input.iter().map(|l| hash_set_matches(l)).sum::<usize>()
+    }),
+    ("bitset", || {
+      input
+        .iter()
+        .map(|l| Card::from_line(l).get_matches())
+        .sum::<usize>()
+    }),
+  );
+}
+
+pub fn solve(args: &[String]) {
+  let input = read_data(cli::has_flag(args, "--test"));
+  if cli::has_flag(args, "--bench") {
+    bench(&input, 100);
+    return;
+  }
+  let issues = validate(&input);
+  if cli::refuse_invalid(args, "scratchcard list", &issues) {
+    return;
+  }
+  if issues.iter().any(|i| matches!(i, Issue::Malformed { .. })) {
+    eprintln!("Cannot score malformed cards, even with --force");
+    return;
+  }
+  if cli::has_flag(args, "--trace") {
+    let csv = cli::has_flag(args, "--csv");
+    if csv {
+      println!("{CSV_HEADER}");
+    }
+    for t in trace(input) {
+      if csv {
+        println!("{}", t.to_csv());
+      } else {
+        println!("{}", t.to_text());
       }
-      next_card.unwrap().amount += amount;
     }
+    return;
   }
-  cards.iter().map(|c| c.amount).sum()
-}
-
-pub fn solve() {
-  let input = read_data(false);
   let score = extra(input);
   println!("{score}")
 }
@@ -111,6 +388,7 @@ pub fn solve() {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use indoc::indoc;
 
   #[test]
   fn simple() {
@@ -125,4 +403,125 @@ mod tests {
     let score = extra(input);
     assert_eq!(score, 30)
   }
+
+  #[test]
+  fn number_set() {
+    let a: NumberSet = [1, 5, 127, 128, 500, 500].into_iter().collect();
+    let b: NumberSet = [5, 127, 500, 1000].into_iter().collect();
+    assert_eq!(a.sparse, vec![128, 500]);
+    assert_eq!(a.intersection_count(&b), 3);
+    assert_eq!(b.intersection_count(&a), 3);
+  }
+
+  #[test]
+  fn bitset_matches_hash_set() {
+    for is_test in [true, false] {
+      let input = read_data(is_test);
+      for l in input.iter() {
+        assert_eq!(Card::from_line(l).get_matches(), hash_set_matches(l));
+      }
+    }
+  }
+
+  #[test]
+  fn validation() {
+    assert_eq!(validate(&read_data(true)), vec![]);
+    assert_eq!(validate(&read_data(false)), vec![]);
+
+    let input: Input = indoc! {"
+      Card 1: 41 48 41 | 83 86  6
+      Card 3: 13 32 20 | 61 30 61 30
+      Card 1:  1 21 53 | 69 82 63
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    let issues = validate(&input);
+    assert_eq!(
+      issues,
+      vec![
+        Issue::DuplicateNumber {
+          line: 1,
+          list: "numbers",
+          number: 41
+        },
+        Issue::NonSequentialId {
+          line: 2,
+          expected: 2,
+          found: 3
+        },
+        Issue::DuplicateNumber {
+          line: 2,
+          list: "winning",
+          number: 61
+        },
+        Issue::DuplicateNumber {
+          line: 2,
+          list: "winning",
+          number: 30
+        },
+        Issue::ListLength {
+          line: 2,
+          list: "winning",
+          expected: 3,
+          found: 4
+        },
+        Issue::NonSequentialId {
+          line: 3,
+          expected: 3,
+          found: 1
+        },
+        Issue::DuplicateId {
+          line: 3,
+          id: 1,
+          first_line: 1
+        },
+      ]
+    );
+    assert_eq!(
+      issues[6].to_string(),
+      "line 3: card 1 is already defined on line 1"
+    );
+
+    let input: Input = indoc! {"
+      Card 1: 41 48 | 83 86
+      Card 2: 13 32   61 30
+      Card 3: 13 x3 | 61 30
+      Card: 1 2 | 3 4
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    let issues: Vec<String> = validate(&input).iter().map(Issue::to_string).collect();
+    assert_eq!(
+      issues,
+      vec![
+        "line 2: malformed card, missing '|' between the number lists",
+        "line 3: malformed card, invalid number \"x3\"",
+        "line 4: malformed card, invalid card id \"Card\"",
+      ]
+    );
+  }
+
+  #[test]
+  fn cascade_trace() {
+    let input = read_data(true);
+    let traces = trace(input);
+    assert_eq!(traces.len(), 6);
+    assert_eq!(
+      traces[3],
+      CardTrace {
+        id: 4,
+        matches: 1,
+        received: vec![(1, 1), (2, 2), (3, 4)],
+        amount: 8,
+      }
+    );
+    assert_eq!(
+      traces[3].to_text(),
+      "Card 4: 1 matches, received 1 from card 1, 2 from card 2, 4 from card 3, final count 8"
+    );
+    assert_eq!(traces[3].to_csv(), "4,1,8,1:1;2:2;3:4");
+    assert_eq!(traces[0].to_csv(), "1,4,1,");
+  }
 }
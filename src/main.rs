@@ -1,3 +1,5 @@
+mod bench;
+mod cli;
 mod eight;
 mod eleven;
 mod five;
@@ -14,21 +16,22 @@ mod two;
 use std::env;
 fn main() {
   let args: Vec<String> = env::args().collect();
-  if args.len() != 2 {
+  if args.len() < 2 {
     println!("Invalid number of arguments")
   }
 
   let day: &str = args.get(1).unwrap();
+  let options = &args[2..];
 
   match day {
     "1" => crate::one::solve(),
-    "2" => crate::two::solve(),
-    "3" => crate::three::solve(),
-    "4" => crate::four::solve(),
-    "5" => crate::five::solve(),
-    "6" => crate::six::solve(),
-    "7" => crate::seven::solve(),
-    "8" => crate::eight::solve(),
+    "2" => crate::two::solve(options),
+    "3" => crate::three::solve(options),
+    "4" => crate::four::solve(options),
+    "5" => crate::five::solve(options),
+    "6" => crate::six::solve(options),
+    "7" => crate::seven::solve(options),
+    "8" => crate::eight::solve(options),
     "9" => crate::nine::solve(),
     "10" => crate::ten::solve(),
     "11" => crate::eleven::solve(),
@@ -1,5 +1,6 @@
+use crate::cli;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
 
@@ -14,18 +15,12 @@ fn read_data(is_test: bool) -> Input {
   line_iter.map(|l| l.unwrap()).collect()
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum HandType {
-  HighCard,
-  Pair,
-  DoublePair,
-  Three,
-  Full,
-  Four,
-  Five,
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct HandType {
+  signature: Vec<usize>,
 }
 
-type Cards = [char; 5];
+type Cards = Vec<char>;
 
 #[derive(Debug)]
 struct Hand {
@@ -33,36 +28,34 @@ struct Hand {
   bid: i64,
   kind: HandType,
   joker: bool,
+  replacement: Option<char>,
 }
 
 fn string_to_cards(s: &str) -> Cards {
-  let mut cards: Cards = ['0'; 5];
-  for (i, c) in s.chars().enumerate() {
-    cards[i] = c;
-  }
-  cards
+  s.chars().collect()
 }
 
 fn cards_to_string(s: &Cards) -> String {
-  String::from_utf8(s.iter().map(|&c| c as u8).collect::<Vec<u8>>()).unwrap()
+  s.iter().collect()
 }
 
 impl Hand {
   fn from_line(line: &str, joker: bool) -> Self {
     let mut parts = line.split_whitespace();
     let cards_input = parts.next().unwrap();
-    let mut cards: Cards = string_to_cards(&cards_input);
+    let cards: Cards = string_to_cards(cards_input);
     let bid = parts.next().unwrap().parse().unwrap();
-    let kind = if joker {
+    let (kind, replacement) = if joker {
       HandType::from_cards_joker(&cards)
     } else {
-      HandType::from_cards(&cards)
+      (HandType::from_cards(&cards), None)
     };
     Self {
       cards,
       bid,
       kind,
       joker,
+      replacement,
     }
   }
 }
@@ -128,38 +121,49 @@ impl Ord for Hand {
 }
 
 impl HandType {
+  fn from_signature(mut signature: Vec<usize>) -> Self {
+    signature.sort_by(|a, b| b.cmp(a));
+    Self { signature }
+  }
+
   fn from_cards(cards: &Cards) -> Self {
     let map: HashMap<char, usize> = cards.iter().fold(Default::default(), |mut acc, c| {
       *acc.entry(*c).or_insert(0) += 1;
       acc
     });
+    Self::from_signature(map.into_values().collect())
+  }
 
-    if map.values().any(|&c| c == 5) {
-      Self::Five
-    } else if map.values().any(|&c| c == 4) {
-      Self::Four
-    } else if map.values().any(|&c| c == 3) && map.values().any(|&c| c == 2) {
-      Self::Full
-    } else if map.values().any(|&c| c == 3) {
-      Self::Three
-    } else if map.values().filter(|&&v| v == 2).count() == 2 {
-      Self::DoublePair
-    } else if map.values().any(|&c| c == 2) {
-      Self::Pair
+  fn name(&self) -> String {
+    let groups: Vec<String> = self
+      .signature
+      .iter()
+      .take_while(|&&n| n > 1)
+      .map(|&n| match n {
+        2 => "pair".to_string(),
+        3 => "three".to_string(),
+        4 => "four".to_string(),
+        5 => "five".to_string(),
+        6 => "six".to_string(),
+        _ => format!("{n} of a kind"),
+      })
+      .collect();
+    if groups.is_empty() {
+      "high card".to_string()
     } else {
-      Self::HighCard
+      groups.join(" plus ")
     }
   }
 
-  fn from_cards_joker(cards: &Cards) -> Self {
+  fn from_cards_joker(cards: &Cards) -> (Self, Option<char>) {
     let map: HashMap<char, usize> = cards.iter().fold(Default::default(), |mut acc, c| {
       *acc.entry(*c).or_insert(0) += 1;
       acc
     });
 
-    let has_joker = map.get(&'J').is_some();
+    let has_joker = map.contains_key(&'J');
     if !has_joker {
-      return Self::from_cards(cards);
+      return (Self::from_cards(cards), None);
     }
 
     let possible_values: Vec<char> = map
@@ -170,60 +174,130 @@ impl HandType {
     let cards_string: String = cards_to_string(cards);
     possible_values
       .iter()
-      .map(|&c| cards_string.replace("J", c.to_string().as_str()))
-      .map(|s| string_to_cards(&s))
-      .map(|c| HandType::from_cards(&c))
-      .max()
-      .unwrap_or(HandType::Five)
+      .map(|&c| {
+        let replaced = string_to_cards(&cards_string.replace("J", c.to_string().as_str()));
+        (HandType::from_cards(&replaced), Some(c))
+      })
+      .max_by_key(|(kind, c)| (kind.clone(), c.map(card_to_point_joker)))
+      .unwrap_or((HandType::from_signature(vec![cards.len()]), Some('A')))
   }
 }
 
-fn initial(input: Input) -> i64 {
-  let mut hands: Vec<_> = input
+fn parse_hands(input: &Input, joker: bool) -> Result<Vec<Hand>, String> {
+  let hands: Vec<Hand> = input
     .iter()
     .map(String::as_ref)
-    .map(|s| Hand::from_line(s, false))
+    .map(|s| Hand::from_line(s, joker))
     .collect();
+  if let Some(first) = hands.first() {
+    let size = first.cards.len();
+    if let Some((i, h)) = hands
+      .iter()
+      .enumerate()
+      .find(|(_, h)| h.cards.len() != size)
+    {
+      return Err(format!(
+        "line {}: hand {} has {} cards, expected {} like line 1",
+        i + 1,
+        cards_to_string(&h.cards),
+        h.cards.len(),
+        size
+      ));
+    }
+  }
+  Ok(hands)
+}
+
+fn ranking(input: &Input, joker: bool) -> Result<Vec<Hand>, String> {
+  let mut hands = parse_hands(input, joker)?;
   hands.sort();
-  hands
-    .iter()
-    .enumerate()
-    // .inspect(|p| {
-    //   dbg!(p.1.cards.as_slice());
-    // })
-    .map(|(i, h)| (i as i64 + 1) * h.bid)
-    .sum()
+  Ok(hands)
 }
 
+fn winnings(input: &Input, joker: bool) -> Result<i64, String> {
+  let hands = ranking(input, joker)?;
+  Ok(
+    hands
+      .iter()
+      .enumerate()
+      .map(|(i, h)| (i as i64 + 1) * h.bid)
+      .sum(),
+  )
+}
+
+fn format_report(hands: &[Hand]) -> String {
+  let mut lines: Vec<String> = vec![];
+  let mut histogram: BTreeMap<&HandType, usize> = BTreeMap::new();
+  let mut total = 0;
+  for (i, h) in hands.iter().enumerate() {
+    let rank = i as i64 + 1;
+    let contribution = rank * h.bid;
+    total += contribution;
+    *histogram.entry(&h.kind).or_insert(0) += 1;
+    let replacement = h
+      .replacement
+      .map(|c| format!(" (J as {c})"))
+      .unwrap_or_default();
+    lines.push(format!(
+      "rank {rank}: {} {}{replacement}, bid {}, winnings {contribution}",
+      cards_to_string(&h.cards),
+      h.kind.name(),
+      h.bid
+    ));
+  }
+  lines.push(format!("total winnings {total}"));
+  lines.push("hand types:".to_string());
+  for (kind, count) in histogram.iter().rev() {
+    lines.push(format!("  {:<16} {count:>5}", kind.name()));
+  }
+  lines.join("\n")
+}
+
+fn initial(input: Input) -> i64 {
+  winnings(&input, false).unwrap()
+}
+
+#[cfg(test)]
 fn extra(input: Input) -> i64 {
-  let mut hands: Vec<_> = input
-    .iter()
-    .map(String::as_ref)
-    .map(|s| Hand::from_line(s, true))
-    .collect();
-  hands.sort();
-  hands
-    .iter()
-    .enumerate()
-    .map(|(i, h)| (i as i64 + 1) * h.bid)
-    .sum()
+  winnings(&input, true).unwrap()
 }
 
-pub fn solve() {
-  let input = read_data(false);
-  let score = extra(input);
-  println!("{score}")
+pub fn solve(args: &[String]) {
+  let input = read_data(cli::has_flag(args, "--test"));
+  let joker = match cli::get_option(args, "--rules") {
+    None | Some("joker") => true,
+    Some("standard") => false,
+    Some(rules) => {
+      eprintln!("Invalid --rules {rules:?}, expected standard or joker");
+      return;
+    }
+  };
+  if cli::has_flag(args, "--report") {
+    match ranking(&input, joker) {
+      Ok(hands) => println!("{}", format_report(&hands)),
+      Err(e) => eprintln!("Invalid hands: {e}"),
+    }
+    return;
+  }
+  match winnings(&input, joker) {
+    Ok(score) => println!("{score}"),
+    Err(e) => eprintln!("Invalid hands: {e}"),
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use indoc::indoc;
 
   #[test]
   fn hand() {
-    let mut a = vec![HandType::Four, HandType::Pair, HandType::Three];
+    let four = HandType::from_signature(vec![4, 1]);
+    let pair = HandType::from_signature(vec![1, 2, 1, 1]);
+    let three = HandType::from_signature(vec![1, 3, 1]);
+    let mut a = vec![four.clone(), pair.clone(), three.clone()];
     a.sort();
-    assert_eq!(a, [HandType::Pair, HandType::Three, HandType::Four]);
+    assert_eq!(a, [pair, three, four]);
     let mut a = vec![1, 2];
     let mut b = vec![2, 1];
     b.sort();
@@ -248,6 +322,85 @@ mod tests {
     assert_eq!(score.len(), 1000)
   }
 
+  #[test]
+  fn signatures() {
+    let cases = [
+      ("23456", "high card"),
+      ("A23A4", "pair"),
+      ("23432", "pair plus pair"),
+      ("TTT98", "three"),
+      ("23332", "three plus pair"),
+      ("AA8AA", "four"),
+      ("AAAAA", "five"),
+      ("AAAA88", "four plus pair"),
+      ("2345678", "high card"),
+    ];
+    for (cards, name) in cases {
+      assert_eq!(HandType::from_cards(&string_to_cards(cards)).name(), name);
+    }
+    let order = [
+      "23456", "A23A4", "23432", "TTT98", "23332", "AA8AA", "AAAAA",
+    ];
+    let kinds: Vec<HandType> = order
+      .iter()
+      .map(|c| HandType::from_cards(&string_to_cards(c)))
+      .collect();
+    assert!(kinds.windows(2).all(|w| w[0] < w[1]));
+    let (joker, replacement) = HandType::from_cards_joker(&string_to_cards("JJJJJJ"));
+    assert_eq!(joker.signature, vec![6]);
+    assert_eq!(replacement, Some('A'));
+  }
+
+  #[test]
+  fn six_card_hands() {
+    let input: Input = indoc! {"
+      AAAA88 10
+      KKKKKK 20
+      234567 5
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    assert_eq!(winnings(&input, false), Ok(5 + 2 * 10 + 3 * 20));
+    let input: Input = indoc! {"
+      AAAA88 10
+      KKKKK 20
+    "}
+    .lines()
+    .map(String::from)
+    .collect();
+    assert_eq!(
+      winnings(&input, false),
+      Err("line 2: hand KKKKK has 5 cards, expected 6 like line 1".to_string())
+    );
+  }
+
+  #[test]
+  fn report() {
+    let input = read_data(true);
+    let hands = ranking(&input, true).unwrap();
+    let report = format_report(&hands);
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "rank 1: 32T3K pair, bid 765, winnings 765");
+    assert_eq!(
+      lines[2],
+      "rank 3: T55J5 four (J as 5), bid 684, winnings 2052"
+    );
+    assert_eq!(
+      lines[4],
+      "rank 5: KTJJT four (J as T), bid 220, winnings 1100"
+    );
+    assert_eq!(lines[5], "total winnings 5905");
+    assert_eq!(lines[7], "  four                 3");
+    assert_eq!(lines[8], "  pair plus pair       1");
+    assert_eq!(lines[9], "  pair                 1");
+
+    let hands = ranking(&input, false).unwrap();
+    let report = format_report(&hands);
+    assert!(report.contains("rank 3: KK677 pair plus pair, bid 28, winnings 84"));
+    assert!(report.contains("total winnings 6440"));
+  }
+
   #[test]
   fn two() {
     let input = read_data(true);
@@ -1,3 +1,5 @@
+use crate::cli;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
 use std::isize;
@@ -27,14 +29,160 @@ impl Game {
     }
   }
 
-  fn play(&self) -> i64 {
-    let range = (1..self.time);
-    let reversed = range.clone().rev();
-    range
-      .zip(reversed)
-      .map(|p| p.0 * p.1)
-      .filter(|n| n.gt(&self.distance))
-      .count() as i64
+  fn play(&self, boat: &Boat) -> i64 {
+    boat
+      .winning_range(self)
+      .map_or(0, |(first, last)| last - first + 1)
+  }
+
+  fn strategy(&self, boat: &Boat) -> Strategy {
+    let best = boat
+      .best_hold(self.time)
+      .map(|hold| (hold, boat.distance(hold, self.time)));
+    Strategy {
+      time: self.time,
+      record: self.distance,
+      wins: boat.winning_range(self),
+      best,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Strategy {
+  time: i64,
+  record: i64,
+  wins: Option<(i64, i64)>,
+  best: Option<(i64, i64)>,
+}
+
+impl Display for Strategy {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "time {}, record {}: ", self.time, self.record)?;
+    match self.wins {
+      Some((earliest, latest)) => write!(
+        f,
+        "win holding {earliest} to {latest} ms ({} ways)",
+        latest - earliest + 1
+      )?,
+      None => write!(f, "no winning hold")?,
+    }
+    match self.best {
+      Some((hold, distance)) => write!(
+        f,
+        ", best hold {hold} ms reaching {distance}, margin {}",
+        distance - self.record
+      ),
+      None => write!(f, ", no valid hold within the race"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Boat {
+  acceleration: i64,
+  max_speed: Option<i64>,
+  min_hold: i64,
+}
+
+impl Default for Boat {
+  fn default() -> Self {
+    Self {
+      acceleration: 1,
+      max_speed: None,
+      min_hold: 0,
+    }
+  }
+}
+
+impl Boat {
+  fn from_args(args: &[String]) -> Result<Self, String> {
+    let get = |name: &str| {
+      cli::get_option(args, name)
+        .map(|v| {
+          v.parse::<i64>()
+            .map_err(|_| format!("invalid {name} {v:?}"))
+        })
+        .transpose()
+    };
+    let default = Self::default();
+    let acceleration = get("--acceleration")?.unwrap_or(default.acceleration);
+    if acceleration < 0 {
+      return Err(format!(
+        "--acceleration must not be negative, found {acceleration}"
+      ));
+    }
+    Ok(Self {
+      acceleration,
+      max_speed: get("--max-speed")?,
+      min_hold: get("--min-hold")?.unwrap_or(default.min_hold),
+    })
+  }
+
+  fn check(&self, game: &Game) -> Result<(), String> {
+    self
+      .acceleration
+      .checked_mul(game.time)
+      .and_then(|speed| speed.checked_mul(game.time))
+      .map(|_| ())
+      .ok_or_else(|| format!("distances overflow in a {} ms race", game.time))
+  }
+
+  fn speed(&self, hold: i64) -> i64 {
+    let speed = self.acceleration * hold;
+    self.max_speed.map_or(speed, |max| speed.min(max))
+  }
+
+  fn distance(&self, hold: i64, time: i64) -> i64 {
+    self.speed(hold) * (time - hold)
+  }
+
+  fn first_hold(&self) -> i64 {
+    self.min_hold.max(0)
+  }
+
+  fn best_hold(&self, time: i64) -> Option<i64> {
+    let (mut lo, mut hi) = (self.first_hold(), time);
+    if lo > hi {
+      return None;
+    }
+    while lo < hi {
+      let mid = lo + (hi - lo) / 2;
+      if self.distance(mid + 1, time) > self.distance(mid, time) {
+        lo = mid + 1;
+      } else {
+        hi = mid;
+      }
+    }
+    Some(lo)
+  }
+
+  fn winning_range(&self, game: &Game) -> Option<(i64, i64)> {
+    let best = self.best_hold(game.time)?;
+    if self.distance(best, game.time) <= game.distance {
+      return None;
+    }
+    let wins = |hold: i64| self.distance(hold, game.time) > game.distance;
+    let (mut lo, mut hi) = (self.first_hold(), best);
+    while lo < hi {
+      let mid = lo + (hi - lo) / 2;
+      if wins(mid) {
+        hi = mid;
+      } else {
+        lo = mid + 1;
+      }
+    }
+    let first = lo;
+    let (mut lo, mut hi) = (best, game.time);
+    while lo < hi {
+      let mid = lo + (hi - lo + 1) / 2;
+      if wins(mid) {
+        lo = mid;
+      } else {
+        hi = mid - 1;
+      }
+    }
+    Some((first, lo))
   }
 }
 
@@ -61,46 +209,65 @@ fn parse_line_extra(l: &str) -> i64 {
     .unwrap()
 }
 
-fn initial(input: Input) -> i64 {
+fn parse_games(input: &Input) -> Vec<Game> {
   let mut input_iter = input.iter();
   let times = parse_line(input_iter.next().unwrap());
   let distances = parse_line(input_iter.next().unwrap());
-  let games: Vec<Game> = times
+  times
     .iter()
     .zip(distances.iter())
     .map(Game::from_pair)
-    .collect();
+    .collect()
+}
+
+fn parse_game_extra(input: &Input) -> Game {
+  let mut input_iter = input.iter();
+  let time = parse_line_extra(input_iter.next().unwrap());
+  let distance = parse_line_extra(input_iter.next().unwrap());
+  Game { time, distance }
+}
 
+fn initial(input: Input, boat: &Boat) -> i64 {
+  let games = parse_games(&input);
   games
     .iter()
-    .map(Game::play)
+    .map(|g| g.play(boat))
     .fold(1, |acc, value| acc * value)
 }
 
-fn extra(input: Input) -> i64 {
-  let mut input_iter = input.iter();
-  let time = parse_line_extra(input_iter.next().unwrap());
-  let distance = parse_line_extra(input_iter.next().unwrap());
-  let mut t: i64 = 1;
-  loop {
-    let remaining = time - t;
-    let d = t * remaining;
-    if d > distance {
-      break;
+fn extra(input: Input, boat: &Boat) -> i64 {
+  parse_game_extra(&input).play(boat)
+}
+
+pub fn solve(args: &[String]) {
+  let input = read_data(cli::has_flag(args, "--test"));
+  let boat = match Boat::from_args(args) {
+    Ok(boat) => boat,
+    Err(e) => {
+      eprintln!("Invalid boat: {e}");
+      return;
     }
-    t += 1;
+  };
+  let mut games = parse_games(&input);
+  games.push(parse_game_extra(&input));
+  if let Err(e) = games.iter().try_for_each(|g| boat.check(g)) {
+    eprintln!("Invalid boat: {e}");
+    return;
   }
-  let half = (time / 2) - t;
-  if time % 2 == 0 {
-    2 * half + 1
-  } else {
-    2 * half
+  if cli::has_flag(args, "--report") {
+    println!("Separate races:");
+    for game in parse_games(&input) {
+      println!("  {}", game.strategy(&boat));
+    }
+    println!("Single race:");
+    println!("  {}", parse_game_extra(&input).strategy(&boat));
+    return;
   }
-}
-
-pub fn solve() {
-  let input = read_data(false);
-  let score = extra(input);
+  let score = if cli::has_flag(args, "--separate") {
+    initial(input, &boat)
+  } else {
+    extra(input, &boat)
+  };
   println!("{score}")
 }
 
@@ -111,14 +278,142 @@ mod tests {
   #[test]
   fn simple() {
     let input = read_data(true);
-    let score = initial(input);
+    let score = initial(input, &Boat::default());
     assert_eq!(score, 288)
   }
 
   #[test]
   fn part2() {
     let input = read_data(true);
-    let score = extra(input);
+    let score = extra(input, &Boat::default());
     assert_eq!(score, 71503)
   }
+
+  #[test]
+  fn strategy() {
+    let input = read_data(true);
+    let games = parse_games(&input);
+    let strategy = games[0].strategy(&Boat::default());
+    assert_eq!(
+      strategy,
+      Strategy {
+        time: 7,
+        record: 9,
+        wins: Some((2, 5)),
+        best: Some((3, 12)),
+      }
+    );
+    assert_eq!(
+      strategy.to_string(),
+      "time 7, record 9: win holding 2 to 5 ms (4 ways), best hold 3 ms reaching 12, margin 3"
+    );
+    let strategy = parse_game_extra(&input).strategy(&Boat::default());
+    assert_eq!(strategy.wins, Some((14, 71516)));
+    assert_eq!(strategy.best, Some((35765, 35765 * 35765)));
+    let game = Game {
+      time: 4,
+      distance: 10,
+    };
+    assert_eq!(game.strategy(&Boat::default()).wins, None);
+    assert_eq!(
+      game.strategy(&Boat::default()).to_string(),
+      "time 4, record 10: no winning hold, best hold 2 ms reaching 4, margin -6"
+    );
+    let game = Game {
+      time: 5,
+      distance: 3,
+    };
+    let boat = Boat {
+      min_hold: 10,
+      ..Default::default()
+    };
+    assert_eq!(
+      game.strategy(&boat),
+      Strategy {
+        time: 5,
+        record: 3,
+        wins: None,
+        best: None,
+      }
+    );
+    assert_eq!(
+      game.strategy(&boat).to_string(),
+      "time 5, record 3: no winning hold, no valid hold within the race"
+    );
+  }
+
+  #[test]
+  fn boat_args() {
+    let args = cli::args("--max-speed 4 --min-hold 2");
+    let expected = Boat {
+      acceleration: 1,
+      max_speed: Some(4),
+      min_hold: 2,
+    };
+    assert_eq!(Boat::from_args(&args), Ok(expected));
+    let args = cli::args("--acceleration fast");
+    assert_eq!(
+      Boat::from_args(&args),
+      Err("invalid --acceleration \"fast\"".to_string())
+    );
+    let args = cli::args("--acceleration -2");
+    assert_eq!(
+      Boat::from_args(&args),
+      Err("--acceleration must not be negative, found -2".to_string())
+    );
+
+    let game = parse_game_extra(&read_data(false));
+    assert_eq!(Boat::default().check(&game), Ok(()));
+    let boat = Boat {
+      acceleration: 1_000_000_000_000,
+      ..Default::default()
+    };
+    assert!(boat.check(&game).is_err());
+  }
+
+  fn brute_force(game: &Game, boat: &Boat) -> i64 {
+    (boat.first_hold()..=game.time)
+      .filter(|&hold| boat.distance(hold, game.time) > game.distance)
+      .count() as i64
+  }
+
+  #[test]
+  fn boat_models() {
+    let boats = [
+      Boat::default(),
+      Boat {
+        acceleration: 3,
+        ..Default::default()
+      },
+      Boat {
+        max_speed: Some(4),
+        ..Default::default()
+      },
+      Boat {
+        acceleration: 2,
+        max_speed: Some(7),
+        min_hold: 3,
+      },
+      Boat {
+        min_hold: 10,
+        ..Default::default()
+      },
+      Boat {
+        acceleration: 0,
+        ..Default::default()
+      },
+    ];
+    for boat in boats.iter() {
+      for time in 0..30 {
+        for distance in 0..80 {
+          let game = Game { time, distance };
+          assert_eq!(
+            game.play(boat),
+            brute_force(&game, boat),
+            "{boat:?} {game:?}"
+          );
+        }
+      }
+    }
+  }
 }
@@ -1,7 +1,10 @@
+use crate::bench;
+use crate::cli;
 use crate::point::Point;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
+use std::str::FromStr;
 
 type Input = Vec<String>;
 
@@ -14,143 +17,389 @@ fn read_data(is_test: bool) -> Input {
   line_iter.map(|l| l.unwrap()).collect()
 }
 
-struct NumberMatch {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Number {
   pos: Point,
-  text: String,
+  len: usize,
+  value: usize,
 }
 
-fn initial(input: Input) -> usize {
-  let dim = Point::new(input.len(), input[0].len());
-  let mut n_list: Vec<usize> = Default::default();
-  for x in 0..dim.x {
-    let mut acc_val: Option<NumberMatch> = None;
-    let acc = &mut acc_val;
-    let mut match_list: Vec<NumberMatch> = Default::default();
-    for (y, c) in input[x].char_indices() {
-      if c.is_digit(10) {
-        if acc.is_none() {
-          *acc = Some(NumberMatch {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Symbol {
+  pos: Point,
+  c: char,
+}
+
+#[derive(Debug)]
+struct Schematic {
+  numbers: Vec<Number>,
+  symbols: Vec<Symbol>,
+  number_symbols: Vec<Vec<usize>>,
+  symbol_numbers: Vec<Vec<usize>>,
+}
+
+impl Number {
+  fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+    (0..self.len).map(|j| Point::new(self.pos.x, self.pos.y + j))
+  }
+}
+
+impl Schematic {
+  fn from_input(input: &Input) -> Self {
+    let dim = Point::new(input.len(), input[0].len());
+    let mut numbers: Vec<Number> = Default::default();
+    let mut symbols: Vec<Symbol> = Default::default();
+    for (x, line) in input.iter().enumerate() {
+      let mut acc: Option<Number> = None;
+      for (y, c) in line.char_indices() {
+        if let Some(d) = c.to_digit(10) {
+          let number = acc.get_or_insert(Number {
             pos: Point::new(x, y),
-            text: c.to_string(),
+            len: 0,
+            value: 0,
           });
-        } else {
-          let number_match = acc.as_mut().unwrap();
-          number_match.text.push(c);
+          number.len += 1;
+          number.value = number.value * 10 + d as usize;
+          continue;
         }
-      } else {
-        if acc.is_some() {
-          match_list.push(acc.take().unwrap());
+        numbers.extend(acc.take());
+        if is_special_char(c) {
+          symbols.push(Symbol {
+            pos: Point::new(x, y),
+            c,
+          });
         }
       }
-
-      let is_last = y == dim.y - 1;
-      if is_last && acc.is_some() {
-        match_list.push(acc.take().unwrap());
-      }
+      numbers.extend(acc.take());
     }
 
-    for m in match_list {
-      let len = m.text.len();
-      let mut points_to_check: Vec<Point> = Vec::with_capacity(len);
-      for j in 0..len {
-        points_to_check.push(Point::new(m.pos.x, m.pos.y + j));
-      }
+    let symbol_at: HashMap<Point, usize> = symbols
+      .iter()
+      .enumerate()
+      .map(|(i, s)| (s.pos, i))
+      .collect();
+    let mut symbol_numbers: Vec<Vec<usize>> = vec![vec![]; symbols.len()];
+    let number_symbols: Vec<Vec<usize>> = numbers
+      .iter()
+      .enumerate()
+      .map(|(i, n)| {
+        let mut around: Vec<usize> = n
+          .cells()
+          .flat_map(|p| p.get_points_around_indirect())
+          .flatten()
+          .filter(|p| p.is_contained_in(&dim))
+          .filter_map(|p| symbol_at.get(&p).copied())
+          .collect();
+        around.sort();
+        around.dedup();
+        for &s in around.iter() {
+          symbol_numbers[s].push(i);
+        }
+        around
+      })
+      .collect();
 
-      let is_valid: bool = points_to_check.iter().any(|point| {
-        point
-          .get_points_around_indirect()
-          .iter()
-          .filter_map(|v| *v)
-          .filter(|v| v.is_contained_in(&dim))
-          .find(|p| is_special_char(input[p.x].chars().nth(p.y).unwrap()))
-          .is_some()
-      });
-      if is_valid {
-        let n = m.text.parse::<usize>().unwrap();
-        n_list.push(n);
-      }
+    Self {
+      numbers,
+      symbols,
+      number_symbols,
+      symbol_numbers,
     }
   }
-  n_list.iter().sum()
-}
 
-fn extra(input: Input) -> usize {
-  let dim = Point::new(input.len(), input[0].len());
-  let mut gear_map: HashMap<Point, HashSet<usize>> = Default::default();
-
-  for x in 0..dim.x {
-    let mut acc_val: Option<NumberMatch> = None;
-    let acc = &mut acc_val;
-    let mut match_list: Vec<NumberMatch> = Default::default();
-    for (y, c) in input[x].char_indices() {
-      if c.is_digit(10) {
-        if acc.is_none() {
-          *acc = Some(NumberMatch {
-            pos: Point::new(x, y),
-            text: c.to_string(),
-          });
-        } else {
-          let number_match = acc.as_mut().unwrap();
-          number_match.text.push(c);
+  fn part_numbers(&self) -> impl Iterator<Item = &Number> + '_ {
+    self
+      .numbers
+      .iter()
+      .zip(self.number_symbols.iter())
+      .filter(|(_, around)| !around.is_empty())
+      .map(|(n, _)| n)
+  }
+
+  fn query_matches<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = (usize, usize)> + 'a {
+    self
+      .symbols
+      .iter()
+      .zip(self.symbol_numbers.iter())
+      .enumerate()
+      .filter(|(_, (s, _))| query.symbols.matches(s.c))
+      .filter(|(_, (_, around))| match query.parts {
+        Some(n) => around.len() == n,
+        None => !around.is_empty(),
+      })
+      .map(|(i, (_, around))| {
+        let values = around.iter().map(|&n| self.numbers[n].value);
+        (i, query.aggregation.apply(values))
+      })
+  }
+
+  fn query(&self, query: &Query) -> usize {
+    self.query_matches(query).map(|(_, v)| v).sum()
+  }
+
+  fn render(&self, input: &Input, colour: bool) -> String {
+    let number_at: HashMap<Point, usize> = self
+      .numbers
+      .iter()
+      .enumerate()
+      .map(|(i, n)| (n.pos, i))
+      .collect();
+    let gear_at: HashMap<Point, usize> = self
+      .query_matches(&Query::gears())
+      .map(|(i, ratio)| (self.symbols[i].pos, ratio))
+      .collect();
+
+    let mut out = String::new();
+    for (x, line) in input.iter().enumerate() {
+      let chars: Vec<char> = line.chars().collect();
+      let mut ratios: Vec<usize> = vec![];
+      let mut y = 0;
+      while y < chars.len() {
+        let p = Point::new(x, y);
+        if let Some(&i) = number_at.get(&p) {
+          let n = &self.numbers[i];
+          let style = if self.number_symbols[i].is_empty() {
+            Style::Isolated
+          } else {
+            Style::Part
+          };
+          let text: String = chars[y..y + n.len].iter().collect();
+          out.push_str(&style.apply(&text, colour));
+          y += n.len;
+          continue;
         }
-      } else {
-        if acc.is_some() {
-          match_list.push(acc.take().unwrap());
+        if let Some(&ratio) = gear_at.get(&p) {
+          out.push_str(&Style::Gear.apply("*", colour));
+          ratios.push(ratio);
+        } else {
+          out.push(chars[y]);
         }
+        y += 1;
       }
+      if !ratios.is_empty() {
+        let ratios: Vec<String> = ratios.iter().map(usize::to_string).collect();
+        out.push_str(&format!("  gear ratio {}", ratios.join(", ")));
+      }
+      out.push('\n');
+    }
+    out
+  }
+}
 
-      let is_last = y == dim.y - 1;
-      if is_last && acc.is_some() {
-        match_list.push(acc.take().unwrap());
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+  Part,
+  Isolated,
+  Gear,
+}
+
+impl Style {
+  fn apply(&self, text: &str, colour: bool) -> String {
+    if colour {
+      let code = match self {
+        Self::Part => "32",
+        Self::Isolated => "31",
+        Self::Gear => "1;33",
+      };
+      format!("\x1b[{code}m{text}\x1b[0m")
+    } else {
+      match self {
+        Self::Part => format!("[{text}]"),
+        Self::Isolated => format!("({text})"),
+        Self::Gear => format!("<{text}>"),
       }
     }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SymbolClass {
+  Any,
+  OneOf(String),
+}
+
+impl SymbolClass {
+  fn matches(&self, c: char) -> bool {
+    match self {
+      Self::Any => true,
+      Self::OneOf(chars) => chars.contains(c),
+    }
+  }
+}
+
+impl FromStr for SymbolClass {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "any" => Ok(Self::Any),
+      _ if !s.is_empty() && s.chars().all(is_special_char) => Ok(Self::OneOf(s.to_string())),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregation {
+  Sum,
+  Product,
+  Count,
+}
+
+impl Aggregation {
+  fn apply(&self, values: impl Iterator<Item = usize>) -> usize {
+    match self {
+      Self::Sum => values.sum(),
+      Self::Product => values.product(),
+      Self::Count => values.count(),
+    }
+  }
+}
+
+impl FromStr for Aggregation {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "sum" => Ok(Self::Sum),
+      "product" => Ok(Self::Product),
+      "count" => Ok(Self::Count),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Query {
+  symbols: SymbolClass,
+  parts: Option<usize>,
+  aggregation: Aggregation,
+}
+
+impl Query {
+  fn gears() -> Self {
+    Self {
+      symbols: SymbolClass::OneOf("*".to_string()),
+      parts: Some(2),
+      aggregation: Aggregation::Product,
+    }
+  }
 
-    for m in match_list {
-      let len = m.text.len();
-      let mut points_to_check: Vec<Point> = Vec::with_capacity(len);
-      for j in 0..len {
-        points_to_check.push(Point::new(m.pos.x, m.pos.y + j));
+  fn from_args(args: &[String]) -> Result<Option<Self>, String> {
+    let Some(symbols) = cli::get_option(args, "--symbols") else {
+      return Ok(None);
+    };
+    let symbols =
+      SymbolClass::from_str(symbols).map_err(|_| format!("invalid --symbols {symbols:?}"))?;
+    let parts = cli::get_option(args, "--parts")
+      .filter(|&p| p != "any")
+      .map(|p| {
+        p.parse::<usize>()
+          .map_err(|_| format!("invalid --parts {p:?}"))
+      })
+      .transpose()?;
+    let aggregation = cli::get_option(args, "--aggregate")
+      .map(|a| Aggregation::from_str(a).map_err(|_| format!("invalid --aggregate {a:?}")))
+      .transpose()?
+      .unwrap_or(Aggregation::Sum);
+    Ok(Some(Self {
+      symbols,
+      parts,
+      aggregation,
+    }))
+  }
+}
+
+fn symbol_mask(input: &Input) -> Vec<Vec<bool>> {
+  let mut mask: Vec<Vec<bool>> = input.iter().map(|l| vec![false; l.len()]).collect();
+  for (x, line) in input.iter().enumerate() {
+    for (y, &b) in line.as_bytes().iter().enumerate() {
+      if !is_special_char(b as char) {
+        continue;
+      }
+      for row in mask.iter_mut().take(x + 2).skip(x.saturating_sub(1)) {
+        for cell in row.iter_mut().take(y + 2).skip(y.saturating_sub(1)) {
+          *cell = true;
+        }
       }
+    }
+  }
+  mask
+}
 
-      points_to_check
-        .iter()
-        .map(|point| {
-          point
-            .get_points_around_indirect()
-            .iter()
-            .filter_map(|v| *v)
-            .filter(|v| v.is_contained_in(&dim))
-            .filter(|p| is_gear(input[p.x].chars().nth(p.y).unwrap()))
-            .collect::<HashSet<_>>()
-        })
-        .reduce(|a, b| a.union(&b).cloned().collect::<HashSet<_>>())
-        .unwrap_or_default()
-        .iter()
-        .for_each(|p| {
-          let n = m.text.parse::<usize>().unwrap();
-          gear_map.entry(*p).or_insert_with(HashSet::new).insert(n);
-        });
+fn part_number_sum(input: &Input) -> usize {
+  let mask = symbol_mask(input);
+  let mut sum = 0;
+  for (line, row) in input.iter().zip(mask.iter()) {
+    let mut value = 0;
+    let mut adjacent = false;
+    for (&b, &marked) in line.as_bytes().iter().zip(row.iter()) {
+      if b.is_ascii_digit() {
+        value = value * 10 + (b - b'0') as usize;
+        adjacent |= marked;
+        continue;
+      }
+      if adjacent {
+        sum += value;
+      }
+      value = 0;
+      adjacent = false;
+    }
+    if adjacent {
+      sum += value;
     }
   }
+  sum
+}
 
-  gear_map
-    .iter()
-    .map(|(_, v)| v)
-    .filter(|map| map.len() == 2)
-    .map(|map| map.iter().cloned().reduce(|a, b| a * b).unwrap())
-    .sum()
+fn initial(input: Input) -> usize {
+  part_number_sum(&input)
 }
 
-fn is_special_char(c: char) -> bool {
-  !c.is_digit(10) && c != '.'
+fn bench(input: &Input, iterations: u32) {
+  bench::compare(
+    iterations,
+    ("schematic scan", || {
+      let schematic = Schematic::from_input(input);
+      schematic.part_numbers().map(|n| n.value).sum::<usize>()
+    }),
+    ("mask scan", || part_number_sum(input)),
+  );
 }
 
-fn is_gear(c: char) -> bool {
-  c == '*'
+fn extra(input: Input) -> usize {
+  let schematic = Schematic::from_input(&input);
+  schematic.query(&Query::gears())
 }
 
-pub fn solve() {
-  let input = read_data(false);
+fn is_special_char(c: char) -> bool {
+  !c.is_digit(10) && c != '.'
+}
+
+pub fn solve(args: &[String]) {
+  let input = read_data(cli::has_flag(args, "--test"));
+  if cli::has_flag(args, "--render") {
+    let schematic = Schematic::from_input(&input);
+    let colour = !cli::has_flag(args, "--no-colour");
+    print!("{}", schematic.render(&input, colour));
+    return;
+  }
+  if cli::has_flag(args, "--bench") {
+    bench(&input, 100);
+    return;
+  }
+  match Query::from_args(args) {
+    Ok(Some(query)) => {
+      let schematic = Schematic::from_input(&input);
+      println!("{}", schematic.query(&query));
+      return;
+    }
+    Ok(None) => {}
+    Err(e) => {
+      eprintln!("Invalid query: {e}");
+      return;
+    }
+  }
   let score = extra(input);
   println!("{score}");
 }
@@ -166,10 +415,122 @@ mod tests {
     assert_eq!(score, 4361);
   }
 
+  #[test]
+  fn mask_matches_schematic() {
+    for is_test in [true, false] {
+      let input = read_data(is_test);
+      let schematic = Schematic::from_input(&input);
+      let expected: usize = schematic.part_numbers().map(|n| n.value).sum();
+      assert_eq!(part_number_sum(&input), expected);
+    }
+  }
+
+  #[test]
+  fn schematic() {
+    let input = read_data(true);
+    let schematic = Schematic::from_input(&input);
+    assert_eq!(schematic.numbers.len(), 10);
+    assert_eq!(schematic.symbols.len(), 6);
+    assert_eq!(
+      schematic.numbers[0],
+      Number {
+        pos: Point::new(0, 0),
+        len: 3,
+        value: 467
+      }
+    );
+    assert_eq!(
+      schematic.symbols[0],
+      Symbol {
+        pos: Point::new(1, 3),
+        c: '*'
+      }
+    );
+    assert_eq!(schematic.symbol_numbers[0], vec![0, 2]);
+    assert!(schematic.number_symbols[1].is_empty());
+  }
+
   #[test]
   fn part_two() {
     let input = read_data(true);
     let score = extra(input);
     assert_eq!(score, 467835);
   }
+
+  #[test]
+  fn queries() {
+    let input = read_data(true);
+    let schematic = Schematic::from_input(&input);
+    let query = Query {
+      symbols: SymbolClass::OneOf("*".to_string()),
+      parts: None,
+      aggregation: Aggregation::Count,
+    };
+    assert_eq!(schematic.query(&query), 5);
+    let query = Query {
+      symbols: SymbolClass::Any,
+      parts: Some(1),
+      aggregation: Aggregation::Sum,
+    };
+    assert_eq!(schematic.query(&query), 4361 - 467 - 35 - 755 - 598);
+    let query = Query {
+      symbols: SymbolClass::from_str("#+").unwrap(),
+      parts: Some(1),
+      aggregation: Aggregation::Sum,
+    };
+    assert_eq!(schematic.query(&query), 633 + 592);
+    assert_eq!(SymbolClass::from_str("1"), Err(()));
+  }
+
+  #[test]
+  fn query_args() {
+    let args = cli::args("--symbols # --parts 3 --aggregate count");
+    let expected = Query {
+      symbols: SymbolClass::OneOf("#".to_string()),
+      parts: Some(3),
+      aggregation: Aggregation::Count,
+    };
+    assert_eq!(Query::from_args(&args), Ok(Some(expected)));
+    assert_eq!(Query::from_args(&[]), Ok(None));
+    let args = cli::args("--symbols * --parts two");
+    assert_eq!(
+      Query::from_args(&args),
+      Err("invalid --parts \"two\"".to_string())
+    );
+  }
+
+  #[test]
+  fn render() {
+    let input = read_data(true);
+    let schematic = Schematic::from_input(&input);
+    let text = schematic.render(&input, false);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[0], "[467]..(114)..");
+    assert_eq!(lines[1], "...<*>......  gear ratio 16345");
+    assert_eq!(lines[4], "[617]*......");
+    let text = schematic.render(&input, true);
+    assert!(text.starts_with("\x1b[32m467\x1b[0m..\x1b[31m114\x1b[0m.."));
+  }
+
+  #[test]
+  fn render_real_input() {
+    let input = read_data(false);
+    let text = Schematic::from_input(&input).render(&input, false);
+    let stripped: Vec<String> = text
+      .lines()
+      .map(|l| l.split("  gear ratio ").next().unwrap())
+      .map(|l| l.chars().filter(|c| !"[]()<>".contains(*c)).collect())
+      .collect();
+    assert_eq!(stripped, input);
+  }
+
+  #[test]
+  fn gear_between_equal_numbers() {
+    let input: Input = vec!["5*5".to_string(), "...".to_string()];
+    let score = extra(input.clone());
+    assert_eq!(score, 25);
+    let score = initial(input);
+    assert_eq!(score, 10);
+  }
 }
@@ -1,9 +1,11 @@
+use crate::cli;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
 
 type Input = Vec<String>;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq)]
 struct GameInfo {
   id: usize,
   game: Vec<BallSet>,
@@ -32,6 +34,120 @@ impl BallSet {
       g: self.g.max(other.g),
     }
   }
+
+  fn get_excess(&self, bag: &Self) -> Self {
+    Self {
+      r: self.r.saturating_sub(bag.r),
+      b: self.b.saturating_sub(bag.b),
+      g: self.g.saturating_sub(bag.g),
+    }
+  }
+
+  fn is_empty(&self) -> bool {
+    self.r == 0 && self.b == 0 && self.g == 0
+  }
+}
+
+impl Display for BallSet {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let parts: Vec<String> = [(self.r, "red"), (self.g, "green"), (self.b, "blue")]
+      .iter()
+      .filter(|(n, _)| *n > 0)
+      .map(|(n, name)| format!("{n} {name}"))
+      .collect();
+    write!(f, "{}", parts.join(", "))
+  }
+}
+
+impl Display for GameInfo {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let sets: Vec<String> = self.game.iter().map(BallSet::to_string).collect();
+    write!(f, "Game {}: {}", self.id, sets.join("; "))
+  }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct DrawExcess {
+  draw: usize,
+  excess: BallSet,
+}
+
+#[derive(Debug)]
+struct GameReport {
+  id: usize,
+  minimal: BallSet,
+  excesses: Vec<DrawExcess>,
+}
+
+impl GameReport {
+  fn from_game(info: &GameInfo, bag: &BallSet) -> Self {
+    let minimal = info
+      .game
+      .iter()
+      .fold(BallSet::default(), |a, b| a.get_union(b));
+    let excesses = info
+      .game
+      .iter()
+      .enumerate()
+      .map(|(draw, ball_set)| DrawExcess {
+        draw,
+        excess: ball_set.get_excess(bag),
+      })
+      .filter(|e| !e.excess.is_empty())
+      .collect();
+    Self {
+      id: info.id,
+      minimal,
+      excesses,
+    }
+  }
+
+  fn is_possible(&self) -> bool {
+    self.excesses.is_empty()
+  }
+
+  fn to_text(&self) -> String {
+    let status = if self.is_possible() {
+      "possible"
+    } else {
+      "impossible"
+    };
+    let mut text = format!(
+      "Game {}: {}, minimal bag {} red, {} green, {} blue",
+      self.id, status, self.minimal.r, self.minimal.g, self.minimal.b
+    );
+    for e in self.excesses.iter() {
+      let colours: Vec<String> = [
+        ("red", e.excess.r),
+        ("green", e.excess.g),
+        ("blue", e.excess.b),
+      ]
+      .iter()
+      .filter(|(_, n)| *n > 0)
+      .map(|(name, n)| format!("{name} +{n}"))
+      .collect();
+      text.push_str(&format!("\n  draw {}: {}", e.draw, colours.join(", ")));
+    }
+    text
+  }
+
+  fn to_machine(&self) -> String {
+    let mut text = format!(
+      "game id={} possible={} min_r={} min_g={} min_b={}",
+      self.id,
+      self.is_possible(),
+      self.minimal.r,
+      self.minimal.g,
+      self.minimal.b
+    );
+    for e in self.excesses.iter() {
+      text.push_str(&format!(
+        "\nexcess id={} draw={} r={} g={} b={}",
+        self.id, e.draw, e.excess.r, e.excess.g, e.excess.b
+      ));
+    }
+    text
+  }
 }
 
 impl GameInfo {
@@ -70,17 +186,19 @@ fn read_data() -> Input {
   line_iter.map(|l| l.unwrap()).collect()
 }
 
+const BAG: BallSet = BallSet {
+  r: 12,
+  g: 13,
+  b: 14,
+};
+
 fn initial(input: Input) -> usize {
   let game_infos: Vec<GameInfo> = input
     .iter()
     .map(|a| a.as_str())
     .map(GameInfo::from_line)
     .collect();
-  let max = BallSet {
-    r: 12,
-    g: 13,
-    b: 14,
-  };
+  let max = BAG;
   let score: usize = game_infos
     .iter()
     .filter(|&info| {
@@ -113,8 +231,27 @@ fn extra(input: Input) -> usize {
   score
 }
 
-pub fn solve() {
+fn report(input: Input, bag: &BallSet) -> Vec<GameReport> {
+  input
+    .iter()
+    .map(|a| GameInfo::from_line(a.as_str()))
+    .map(|info| GameReport::from_game(&info, bag))
+    .collect()
+}
+
+pub fn solve(args: &[String]) {
   let input = read_data();
+  if cli::has_flag(args, "--report") {
+    let machine = cli::has_flag(args, "--machine");
+    for game_report in report(input, &BAG) {
+      if machine {
+        println!("{}", game_report.to_machine());
+      } else {
+        println!("{}", game_report.to_text());
+      }
+    }
+    return;
+  }
   let score = extra(input);
   println!("{score}")
 }
@@ -147,6 +284,78 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"#;
     assert_eq!(score, 8);
   }
 
+  #[test]
+  fn sample_report() {
+    let reports = report(SAMPLE.lines().map(String::from).collect(), &BAG);
+    let impossible: Vec<usize> = reports
+      .iter()
+      .filter(|r| !r.is_possible())
+      .map(|r| r.id)
+      .collect();
+    assert_eq!(impossible, vec![3, 4]);
+    assert_eq!(reports[2].minimal, BallSet { r: 20, b: 6, g: 13 });
+    assert_eq!(
+      reports[3].excesses,
+      vec![DrawExcess {
+        draw: 2,
+        excess: BallSet { r: 2, b: 1, g: 0 }
+      }]
+    );
+    assert_eq!(
+      reports[3].to_text(),
+      "Game 4: impossible, minimal bag 14 red, 3 green, 15 blue\n  draw 2: red +2, blue +1"
+    );
+    assert_eq!(
+      reports[0].to_machine(),
+      "game id=1 possible=true min_r=4 min_g=2 min_b=6"
+    );
+  }
+
+  #[test]
+  fn display() {
+    let line = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green";
+    let info: GameInfo = GameInfo::from_line(line);
+    assert_eq!(
+      info.to_string(),
+      "Game 1: 4 red, 3 blue; 1 red, 2 green, 6 blue; 2 green"
+    );
+  }
+
+  struct XorShift(u64);
+
+  impl XorShift {
+    fn next(&mut self, max: usize) -> usize {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      (self.0 % max as u64) as usize
+    }
+  }
+
+  #[test]
+  fn display_round_trip() {
+    let mut rng = XorShift(0x2023_1202);
+    for _ in 0..1000 {
+      let id = rng.next(1000) + 1;
+      let game: Vec<BallSet> = (0..rng.next(6) + 1)
+        .map(|_| {
+          let mut ball_set = BallSet {
+            r: rng.next(20),
+            b: rng.next(20),
+            g: rng.next(20),
+          };
+          if ball_set.is_empty() {
+            ball_set.g = rng.next(19) + 1;
+          }
+          ball_set
+        })
+        .collect();
+      let info = GameInfo { id, game };
+      let parsed = GameInfo::from_line(&info.to_string());
+      assert_eq!(parsed, info);
+    }
+  }
+
   #[test]
   fn sample_data_extra() {
     let score = extra(SAMPLE.lines().map(String::from).collect());